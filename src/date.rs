@@ -0,0 +1,789 @@
+use std::time::{Duration, SystemTime};
+
+use anyhow::Context;
+use chrono::{Datelike, FixedOffset, LocalResult, Offset, TimeZone};
+
+/// Parse a -d string, which is either '@' followed by seconds since the epoch
+/// (negative for times before 1970, with up to nine fractional digits), '@now'
+/// or '@today' (local midnight), the asctime() layout many logs use, `date`'s
+/// default output, ISO 8601 in basic or extended form, or anything dateparser
+/// understands.
+pub fn parse_date(date: &str) -> anyhow::Result<SystemTime> {
+    match date.strip_prefix('@') {
+        Some("now") => Ok(SystemTime::now()),
+        Some("today") => today(),
+        Some(epoch) => parse_epoch(epoch),
+        None => match parse_asctime(date)
+            .or_else(|| parse_date_output(date))
+            .or_else(|| parse_iso8601(date))
+        {
+            Some(time) => Ok(time),
+            None => parse_local(&expand_short_years(date)),
+        },
+    }
+}
+
+/// The start of the current day in the local time zone.
+fn today() -> anyhow::Result<SystemTime> {
+    let midnight = chrono::Local::now()
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .expect("midnight is a valid time");
+    resolve_local(&midnight)
+        .map(SystemTime::from)
+        .context("local midnight does not exist today")
+}
+
+/// Parse a local time laid out like asctime(), e.g. 'Mon Jan 15 10:00:00 2024'
+/// (the day of the month may be space padded).
+fn parse_asctime(date: &str) -> Option<SystemTime> {
+    let naive = chrono::NaiveDateTime::parse_from_str(date.trim(), "%a %b %e %H:%M:%S %Y").ok()?;
+    resolve_local(&naive).map(SystemTime::from)
+}
+
+/// Parse `date`'s default output, e.g. 'Mon Jan 15 10:00:00 UTC 2024': the
+/// asctime() layout with a time zone abbreviation before the year. The local
+/// zone's own abbreviations are read as the local time they name, so that
+/// 'EDT' and 'EST' both work in New York; any other is looked up in
+/// `zone_offset`.
+fn parse_date_output(date: &str) -> Option<SystemTime> {
+    let words: Vec<&str> = date.split_whitespace().collect();
+    let [weekday, month, day, clock, zone, year] = words[..] else {
+        return None;
+    };
+    let naive = chrono::NaiveDateTime::parse_from_str(
+        &format!("{} {} {} {} {}", weekday, month, day, clock, year),
+        "%a %b %e %H:%M:%S %Y",
+    )
+    .ok()?;
+    let local =
+        resolve_local(&naive).filter(|time| local_abbreviation(time).as_deref() == Some(zone));
+    let time = match local {
+        Some(time) => time,
+        None => FixedOffset::east_opt(zone_offset(zone)?)?
+            .from_local_datetime(&naive)
+            .single()?,
+    };
+    to_system_time(&time)
+}
+
+/// The abbreviation the local time zone uses at `time`, like 'EST' or 'CEST'.
+#[cfg(unix)]
+fn local_abbreviation(time: &chrono::DateTime<FixedOffset>) -> Option<String> {
+    use std::ffi::CStr;
+
+    let secs = libc::time_t::try_from(time.timestamp()).ok()?;
+    // SAFETY: localtime_r fills in `tm`, whose tm_zone then points at a
+    // NUL-terminated string owned by the C library.
+    unsafe {
+        let mut tm: libc::tm = std::mem::zeroed();
+        if libc::localtime_r(&secs, &mut tm).is_null() || tm.tm_zone.is_null() {
+            return None;
+        }
+        Some(CStr::from_ptr(tm.tm_zone).to_string_lossy().into_owned())
+    }
+}
+
+#[cfg(not(unix))]
+fn local_abbreviation(_time: &chrono::DateTime<FixedOffset>) -> Option<String> {
+    None
+}
+
+/// The offset east of UTC, in seconds, for a common time zone abbreviation.
+/// Some abbreviations name more than one zone; these take the one `date` is
+/// most likely to have printed: CST is US Central (not China), IST is India
+/// (not Ireland or Israel) and BST is British Summer Time (not Bangladesh).
+fn zone_offset(zone: &str) -> Option<i32> {
+    let minutes = match zone {
+        "UTC" | "UT" | "GMT" | "Z" | "WET" => 0,
+        "WEST" | "BST" | "CET" => 60,
+        "CEST" | "EET" => 120,
+        "EEST" | "MSK" => 180,
+        "IST" => 330,
+        "JST" | "KST" => 540,
+        "AEST" => 600,
+        "AEDT" => 660,
+        "NZST" => 720,
+        "NZDT" => 780,
+        "EDT" => -240,
+        "EST" | "CDT" => -300,
+        "CST" | "MDT" => -360,
+        "MST" | "PDT" => -420,
+        "PST" | "AKDT" => -480,
+        "AKST" => -540,
+        "HST" => -600,
+        _ => return None,
+    };
+    Some(minutes * 60)
+}
+
+/// Parse an ISO 8601 date and time in extended ('2024-01-15T10:00:00') or basic
+/// ('20240115T100000') form, with optional fractional seconds and an offset of
+/// 'Z', '+HH:MM', '+HHMM' or '+HH'. Without an offset the time is local.
+fn parse_iso8601(date: &str) -> Option<SystemTime> {
+    const LAYOUTS: [&str; 4] = [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M",
+        "%Y%m%dT%H%M%S%.f",
+        "%Y%m%dT%H%M",
+    ];
+    let date = date.trim();
+    let (naive, offset) = match date.strip_suffix(['Z', 'z']) {
+        Some(rest) => (rest, Some(FixedOffset::east_opt(0)?)),
+        None => match date
+            .rfind(['+', '-'])
+            .filter(|&at| date[..at].contains('T'))
+        {
+            Some(at) => (&date[..at], Some(parse_offset(&date[at..])?)),
+            None => (date, None),
+        },
+    };
+    let naive = LAYOUTS
+        .iter()
+        .find_map(|layout| chrono::NaiveDateTime::parse_from_str(naive, layout).ok())?;
+    let time = match offset {
+        Some(offset) => offset.from_local_datetime(&naive).single()?,
+        None => resolve_local(&naive)?,
+    };
+    Some(time.into())
+}
+
+/// Parse a '+HH:MM', '+HHMM' or '+HH' offset from UTC.
+fn parse_offset(offset: &str) -> Option<FixedOffset> {
+    let sign = match offset.as_bytes().first()? {
+        b'+' => 1,
+        b'-' => -1,
+        _ => return None,
+    };
+    let digits = offset[1..].replacen(':', "", 1);
+    if !matches!(digits.len(), 2 | 4) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let hours: i32 = digits[..2].parse().ok()?;
+    let minutes: i32 = match &digits[2..] {
+        "" => 0,
+        minutes => minutes.parse().ok()?,
+    };
+    if minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+/// Parse with dateparser, resolving the local wall-clock times it rejects
+/// because a daylight-saving change makes them ambiguous or nonexistent.
+fn parse_local(date: &str) -> anyhow::Result<SystemTime> {
+    let err = match dateparser::parse(date) {
+        Ok(time) => return Ok(time.into()),
+        Err(err) => err,
+    };
+
+    // A string carrying its own zone parses to the same instant whatever zone
+    // is assumed; only a naive wall-clock time moves with it.
+    let hour_east = FixedOffset::east_opt(3600).expect("one hour is a valid offset");
+    let naive = match (
+        dateparser::parse_with_timezone(date, &chrono::Utc),
+        dateparser::parse_with_timezone(date, &hour_east),
+    ) {
+        (Ok(as_utc), Ok(as_east)) if as_utc != as_east => as_utc.naive_utc(),
+        _ => return Err(err),
+    };
+
+    match resolve_local(&naive) {
+        Some(time) => Ok(time.into()),
+        None => Err(err),
+    }
+}
+
+/// Place a wall-clock time in the local time zone, even when a daylight-saving
+/// change makes it ambiguous or nonexistent:
+///   - an ambiguous time (clocks fall back) takes the earlier of its instants;
+///   - a nonexistent time (clocks spring forward) is read with the offset in
+///     force before the change, so 02:30 in a 02:00-03:00 gap becomes 03:30.
+fn resolve_local(naive: &chrono::NaiveDateTime) -> Option<chrono::DateTime<FixedOffset>> {
+    match chrono::Local.from_local_datetime(naive) {
+        LocalResult::Single(time) => Some(time.fixed_offset()),
+        LocalResult::Ambiguous(one, other) => Some(one.min(other).fixed_offset()),
+        LocalResult::None => {
+            let before = chrono::Local
+                .offset_from_utc_datetime(&(*naive - chrono::Duration::days(1)))
+                .fix();
+            before.from_local_datetime(naive).single()
+        }
+    }
+}
+
+/// Spell the locale's AM/PM markers the English way dateparser understands,
+/// taking them from LC_TIME or from `locale` (--locale) if given. `parse_date`
+/// does not do this itself.
+pub fn english_meridiem(date: &str, locale: Option<&str>) -> anyhow::Result<String> {
+    Ok(match meridiem_markers(locale)? {
+        Some((am, pm)) => replace_meridiem(date, &am, &pm),
+        None => date.to_string(),
+    })
+}
+
+/// Replace `am` and `pm` with 'AM' and 'PM' where they stand next to a clock
+/// time, as a word of their own or glued to its end, so the same letters inside
+/// another word (Swedish 'em' in 'December') are left alone. A marker that
+/// comes before the time, as in Korean, is moved after it.
+fn replace_meridiem(date: &str, am: &str, pm: &str) -> String {
+    let english = |word: &str| {
+        let word = word.to_lowercase();
+        [(am, "AM"), (pm, "PM")]
+            .into_iter()
+            .find(|(marker, _)| !marker.is_empty() && word == marker.to_lowercase())
+            .map(|(_, english)| english)
+    };
+    let glued = |word: &str| {
+        word.char_indices()
+            .filter(|&(i, _)| i > 0 && is_clock(&word[..i]))
+            .find_map(|(i, _)| Some(format!("{}{}", &word[..i], english(&word[i..])?)))
+    };
+    let mut words = date.split_whitespace().peekable();
+    let mut replaced: Vec<String> = Vec::new();
+    let mut changed = false;
+    while let Some(word) = words.next() {
+        let after_clock = replaced.last().is_some_and(|last| is_clock(last));
+        let replacement = match english(word) {
+            Some(english) if after_clock => Some(english.to_string()),
+            Some(english) if words.peek().is_some_and(|next| is_clock(next)) => {
+                words.next().map(|clock| format!("{} {}", clock, english))
+            }
+            _ => glued(word),
+        };
+        changed |= replacement.is_some();
+        replaced.push(replacement.unwrap_or_else(|| word.to_string()));
+    }
+    if changed {
+        replaced.join(" ")
+    } else {
+        date.to_string()
+    }
+}
+
+/// Whether `word` reads as a clock time: digits, perhaps split by ':' or '.'.
+fn is_clock(word: &str) -> bool {
+    word.starts_with(|c: char| c.is_ascii_digit())
+        && word
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == ':' || c == '.')
+}
+
+/// The AM and PM markers of `locale`, or of LC_TIME when it is `None`.
+#[cfg(unix)]
+fn meridiem_markers(locale: Option<&str>) -> anyhow::Result<Option<(String, String)>> {
+    use std::ffi::{CStr, CString};
+
+    let name = CString::new(locale.unwrap_or("")).context("invalid locale name")?;
+    // SAFETY: the locale object is made current for this thread alone, the
+    // nl_langinfo strings are copied out while it is, and the thread's previous
+    // locale is put back before the object is freed. Nothing else in the
+    // process sees the change, unlike with setlocale.
+    unsafe {
+        let time_locale = libc::newlocale(libc::LC_TIME_MASK, name.as_ptr(), std::ptr::null_mut());
+        if time_locale.is_null() {
+            return match locale {
+                Some(locale) => anyhow::bail!("unknown locale {:?}", locale),
+                None => Ok(None),
+            };
+        }
+        let previous = libc::uselocale(time_locale);
+        let marker = |item| {
+            CStr::from_ptr(libc::nl_langinfo(item))
+                .to_string_lossy()
+                .into_owned()
+        };
+        let markers = (marker(libc::AM_STR), marker(libc::PM_STR));
+        libc::uselocale(previous);
+        libc::freelocale(time_locale);
+        Ok(Some(markers))
+    }
+}
+
+#[cfg(not(unix))]
+fn meridiem_markers(_locale: Option<&str>) -> anyhow::Result<Option<(String, String)>> {
+    Ok(None)
+}
+
+/// Parse a -t stamp, `[[CC]YY]MMDDhhmm[.ss]`, as a local time. Without a year
+/// the current one is used, and a two-digit year YY of 69 to 99 means 1969 to
+/// 1999 while 00 to 68 means 2000 to 2068, as POSIX has it. Seconds may be 60,
+/// for a leap second, which is read as the first second of the next minute,
+/// and may carry up to nine fractional digits as an extension ('.30.25').
+/// CCYY reaches back to year 0 where the platform's `SystemTime` does.
+pub fn parse_posix_t(stamp: &str) -> anyhow::Result<SystemTime> {
+    let (digits, seconds) = match stamp.split_once('.') {
+        Some((digits, seconds)) => (digits, Some(seconds)),
+        None => (stamp, None),
+    };
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        anyhow::bail!(
+            "invalid time stamp {:?}: expected [[CC]YY]MMDDhhmm[.ss]",
+            stamp
+        );
+    }
+    let (year, rest) = match digits.len() {
+        8 => (chrono::Local::now().year(), digits),
+        10 => (full_year(digits[..2].parse()?), &digits[2..]),
+        12 => (digits[..4].parse()?, &digits[4..]),
+        _ => anyhow::bail!(
+            "invalid time stamp {:?}: expected [[CC]YY]MMDDhhmm[.ss]",
+            stamp
+        ),
+    };
+    let seconds = seconds.map(|ss| match ss.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (ss, None),
+    });
+    let (seconds, nanos): (u32, u32) = match seconds {
+        None => (0, 0),
+        Some((ss, None)) if is_digits(ss, 2..=2) => (ss.parse()?, 0),
+        Some((ss, Some(fraction))) if is_digits(ss, 2..=2) && is_digits(fraction, 1..=9) => {
+            (ss.parse()?, format!("{:0<9}", fraction).parse()?)
+        }
+        Some(_) => anyhow::bail!(
+            "invalid time stamp {:?}: expected two digits of seconds after '.', \
+             optionally with up to nine fractional digits",
+            stamp
+        ),
+    };
+    let field = |at: usize| -> u32 { rest[at..at + 2].parse().expect("checked to be digits") };
+    let naive = chrono::NaiveDate::from_ymd_opt(year, field(0), field(2))
+        .and_then(|day| day.and_hms_opt(field(4), field(6), seconds.min(59)))
+        .filter(|_| seconds <= 60)
+        .with_context(|| format!("invalid time stamp {:?}: no such date or time", stamp))?;
+    let time = resolve_local(&naive)
+        .with_context(|| format!("time stamp {:?} does not exist locally", stamp))?;
+    let leap = Duration::from_secs(u64::from(seconds == 60));
+    to_system_time(&time)
+        .and_then(|time| time.checked_add(leap + Duration::from_nanos(nanos.into())))
+        .with_context(|| format!("time stamp {:?} is out of range on this platform", stamp))
+}
+
+/// The year a two-digit year names under the POSIX rule: 69 to 99 are 1969 to
+/// 1999, and 00 to 68 are 2000 to 2068.
+fn full_year(yy: i32) -> i32 {
+    if yy >= 69 {
+        1900 + yy
+    } else {
+        2000 + yy
+    }
+}
+
+/// Write out two-digit years in full, by `full_year`'s rule, wherever
+/// dateparser would otherwise guess them: in a slashed date like '01/15/69' and
+/// after a month name and day.
+fn expand_short_years(date: &str) -> String {
+    let mut words: Vec<String> = date.split(' ').map(str::to_string).collect();
+    for i in 0..words.len() {
+        let is_month = |word: &str| word.trim_end_matches(',').parse::<chrono::Month>().is_ok();
+        // '15 Jan 69', or 'Jan 15, 69'.
+        let after_month = i > 1
+            && ((is_month(&words[i - 1]) && is_digits(&words[i - 2], 1..=2))
+                || (is_month(&words[i - 2]) && words[i - 1].ends_with(',')));
+        let word = &words[i];
+        let fields: Vec<&str> = word.split('/').collect();
+        let yy = match fields[..] {
+            [_, _, yy] if fields.iter().all(|field| is_digits(field, 1..=2)) => yy,
+            [yy] if after_month => yy,
+            _ => continue,
+        };
+        if !is_digits(yy, 2..=2) {
+            continue;
+        }
+        let year = full_year(yy.parse().expect("checked to be digits"));
+        words[i] = format!("{}{}", &word[..word.len() - 2], year);
+    }
+    words.join(" ")
+}
+
+/// Convert without panicking on a time `SystemTime` cannot hold, such as one
+/// before 1601 on Windows.
+fn to_system_time(time: &chrono::DateTime<FixedOffset>) -> Option<SystemTime> {
+    crate::TimeSpec::At {
+        secs: time.timestamp(),
+        nanos: time.timestamp_subsec_nanos(),
+    }
+    .to_system_time()
+}
+
+/// Whether `text` is all ASCII digits, with a length in `len`.
+fn is_digits(text: &str, len: std::ops::RangeInclusive<usize>) -> bool {
+    len.contains(&text.len()) && text.bytes().all(|b| b.is_ascii_digit())
+}
+
+/// Read a bare 10-digit number as epoch seconds and a 13-digit one as epoch
+/// milliseconds (as JavaScript writes them), for --auto-epoch.
+pub fn parse_auto_epoch(date: &str) -> Option<anyhow::Result<SystemTime>> {
+    if !date.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    match date.len() {
+        10 => Some(parse_epoch(date)),
+        13 => Some(parse_epoch(&format!("{}.{}", &date[..10], &date[10..]))),
+        _ => None,
+    }
+}
+
+/// Parse `SECS[.FRAC]`. A fraction of at most nine digits is exact to the
+/// nanosecond, so nothing is rounded; longer fractions are rejected.
+pub fn parse_epoch(epoch: &str) -> anyhow::Result<SystemTime> {
+    let (whole, fraction) = match epoch.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (epoch, None),
+    };
+    let secs: i64 = whole
+        .parse()
+        .with_context(|| format!("invalid epoch seconds {:?}", epoch))?;
+    let nanos = match fraction {
+        None => 0,
+        Some(digits)
+            if (1..=9).contains(&digits.len()) && digits.bytes().all(|b| b.is_ascii_digit()) =>
+        {
+            format!("{:0<9}", digits).parse()?
+        }
+        Some(_) => anyhow::bail!(
+            "invalid epoch fraction {:?}: expected one to nine digits",
+            epoch
+        ),
+    };
+    let offset = Duration::new(secs.unsigned_abs(), nanos);
+    let time = if whole.starts_with('-') {
+        SystemTime::UNIX_EPOCH.checked_sub(offset)
+    } else {
+        SystemTime::UNIX_EPOCH.checked_add(offset)
+    };
+    time.with_context(|| format!("epoch {} is out of range on this platform", secs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The instant a local wall-clock time names, as tick resolves it.
+    fn local(year: i32, month: u32, day: u32, hour: u32, min: u32, sec: u32) -> SystemTime {
+        let naive = chrono::NaiveDate::from_ymd_opt(year, month, day)
+            .and_then(|day| day.and_hms_opt(hour, min, sec))
+            .expect("valid test date");
+        resolve_local(&naive).expect("resolvable test date").into()
+    }
+
+    fn epoch(secs: u64, nanos: u32) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::new(secs, nanos)
+    }
+
+    #[test]
+    fn negative_epochs_fall_before_1970() {
+        assert_eq!(
+            parse_epoch("-1").unwrap(),
+            SystemTime::UNIX_EPOCH - Duration::from_secs(1)
+        );
+        assert_eq!(
+            parse_epoch("-1.25").unwrap(),
+            SystemTime::UNIX_EPOCH - Duration::from_millis(1250)
+        );
+        assert_eq!(
+            parse_epoch("-0.5").unwrap(),
+            SystemTime::UNIX_EPOCH - Duration::from_millis(500)
+        );
+        assert_eq!(
+            parse_date("@-86400").unwrap(),
+            SystemTime::UNIX_EPOCH - Duration::from_secs(86400)
+        );
+    }
+
+    #[test]
+    fn epoch_fractions_are_exact() {
+        assert_eq!(parse_epoch("0").unwrap(), SystemTime::UNIX_EPOCH);
+        assert_eq!(parse_epoch("1.5").unwrap(), epoch(1, 500_000_000));
+        assert_eq!(
+            parse_epoch("1234567890.5").unwrap(),
+            epoch(1_234_567_890, 500_000_000)
+        );
+        assert_eq!(
+            parse_epoch("1000000000.123456789").unwrap(),
+            epoch(1_000_000_000, 123_456_789)
+        );
+        for bad in ["", "1.", "1.1234567890", "1.2e3", "x"] {
+            assert!(parse_epoch(bad).is_err(), "{:?} was accepted", bad);
+        }
+    }
+
+    #[test]
+    fn asctime_dates_are_local() {
+        assert_eq!(
+            parse_asctime("Mon Jan 15 10:00:00 2024"),
+            Some(local(2024, 1, 15, 10, 0, 0))
+        );
+        assert_eq!(
+            parse_asctime("Fri Jan  5 10:00:00 2024"),
+            Some(local(2024, 1, 5, 10, 0, 0))
+        );
+        assert_eq!(
+            parse_date("Mon Jan 15 10:00:00 2024").unwrap(),
+            local(2024, 1, 15, 10, 0, 0)
+        );
+    }
+
+    #[test]
+    fn asctime_needs_every_field_and_the_right_weekday() {
+        for bad in [
+            "Mon Jan 15 10:00 2024",
+            "Mon Jan 15 10:00:00",
+            "Tue Jan 15 10:00:00 2024",
+        ] {
+            assert_eq!(parse_asctime(bad), None, "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn auto_epoch_reads_seconds_and_milliseconds() {
+        assert_eq!(
+            parse_auto_epoch("1700000000").unwrap().unwrap(),
+            epoch(1_700_000_000, 0)
+        );
+        assert_eq!(
+            parse_auto_epoch("1700000000123").unwrap().unwrap(),
+            epoch(1_700_000_000, 123_000_000)
+        );
+        for other in ["170000000", "17000000001", "2024-01-15", "-1700000000", ""] {
+            assert!(parse_auto_epoch(other).is_none(), "{:?}", other);
+        }
+    }
+
+    #[test]
+    fn meridiem_markers_are_replaced_only_next_to_a_time() {
+        assert_eq!(
+            replace_meridiem("15 December 2024 3:00 em", "fm", "em"),
+            "15 December 2024 3:00 PM"
+        );
+        assert_eq!(
+            replace_meridiem("1/15/2024 10:30EM", "fm", "em"),
+            "1/15/2024 10:30PM"
+        );
+        assert_eq!(replace_meridiem("December 15", "fm", "em"), "December 15");
+        assert_eq!(
+            replace_meridiem("2024-01-15  em", "fm", "em"),
+            "2024-01-15  em"
+        );
+    }
+
+    #[test]
+    fn a_meridiem_marker_before_the_time_moves_after_it() {
+        assert_eq!(
+            replace_meridiem("1/15/2024 오후 3:00", "오전", "오후"),
+            "1/15/2024 3:00 PM"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn locale_markers_make_dates_dateparser_reads() {
+        let afternoon = local(2024, 1, 15, 15, 0, 0);
+        for (locale, date) in [
+            ("el_GR.utf8", "1/15/2024 3:00 μμ"),
+            ("ko_KR.utf8", "1/15/2024 오후 3:00"),
+        ] {
+            let Ok(english) = english_meridiem(date, Some(locale)) else {
+                eprintln!("skipped: the {} locale is not installed", locale);
+                continue;
+            };
+            assert_eq!(parse_date(&english).unwrap(), afternoon, "{}", locale);
+        }
+    }
+
+    #[test]
+    fn at_now_is_the_current_time() {
+        let before = SystemTime::now();
+        let now = parse_date("@now").unwrap();
+        assert!(before <= now && now <= SystemTime::now());
+    }
+
+    #[test]
+    fn at_today_is_local_midnight() {
+        let today = chrono::Local::now().date_naive();
+        let midnight = today.and_hms_opt(0, 0, 0).unwrap();
+        let parsed = chrono::DateTime::<chrono::Local>::from(parse_date("@today").unwrap());
+        // Should midnight pass between the two readings, @today is the next day.
+        assert!(
+            parsed.naive_local() == midnight
+                || parsed.naive_local() == midnight + chrono::Days::new(1)
+        );
+    }
+
+    #[test]
+    fn iso8601_spellings_name_the_same_instant() {
+        let instant = epoch(1_705_312_800, 0);
+        for spelling in [
+            "2024-01-15T10:00:00Z",
+            "2024-01-15T10:00:00z",
+            "2024-01-15T10:00Z",
+            "20240115T100000Z",
+            "20240115T1000Z",
+            "2024-01-15T11:30:00+01:30",
+            "2024-01-15T11:30:00+0130",
+            "2024-01-15T05:00:00-05",
+            "20240115T050000-0500",
+        ] {
+            assert_eq!(parse_iso8601(spelling), Some(instant), "{:?}", spelling);
+            assert_eq!(parse_date(spelling).unwrap(), instant, "{:?}", spelling);
+        }
+        assert_eq!(
+            parse_iso8601("2024-01-15T10:00:00.5Z"),
+            Some(epoch(1_705_312_800, 500_000_000))
+        );
+        assert_eq!(
+            parse_iso8601("2024-01-15T10:00:00"),
+            Some(local(2024, 1, 15, 10, 0, 0))
+        );
+    }
+    #[test]
+    fn iso8601_rejects_bad_offsets() {
+        for bad in [
+            "2024-01-15T10:00:00+1",
+            "2024-01-15T10:00:00+01:60",
+            "2024-01-15T10:00:00+01:3",
+            "2024-01-15",
+        ] {
+            assert_eq!(parse_iso8601(bad), None, "{:?}", bad);
+        }
+    }
+
+    #[test]
+    fn posix_t_two_digit_years_pivot_at_69() {
+        assert_eq!(
+            parse_posix_t("6901151000").unwrap(),
+            local(1969, 1, 15, 10, 0, 0)
+        );
+        assert_eq!(
+            parse_posix_t("6801151000").unwrap(),
+            local(2068, 1, 15, 10, 0, 0)
+        );
+        assert_eq!(
+            parse_posix_t("0001151000").unwrap(),
+            local(2000, 1, 15, 10, 0, 0)
+        );
+    }
+    #[test]
+    fn posix_t_takes_eight_ten_or_twelve_digits() {
+        let this_year = chrono::Local::now().year();
+        assert_eq!(
+            parse_posix_t("01151000").unwrap(),
+            local(this_year, 1, 15, 10, 0, 0)
+        );
+        assert_eq!(
+            parse_posix_t("2401151000.30").unwrap(),
+            local(2024, 1, 15, 10, 0, 30)
+        );
+        assert_eq!(
+            parse_posix_t("202401151000").unwrap(),
+            local(2024, 1, 15, 10, 0, 0)
+        );
+    }
+    #[test]
+    fn posix_t_reads_a_leap_second_as_the_next_minute() {
+        assert_eq!(
+            parse_posix_t("202412312359.60").unwrap(),
+            local(2024, 12, 31, 23, 59, 59) + Duration::from_secs(1)
+        );
+    }
+    #[test]
+    fn posix_t_rejects_malformed_stamps() {
+        for stamp in [
+            "",
+            "202413151000",
+            "0115100",
+            "20240115100",
+            "2024011510000",
+            "2024O1151000",
+            "202401151000.3",
+            "202401151000.61",
+            "202402301000",
+            "202401152500",
+        ] {
+            assert!(parse_posix_t(stamp).is_err(), "{:?} was accepted", stamp);
+        }
+    }
+
+    #[test]
+    fn posix_t_keeps_fractional_seconds() {
+        assert_eq!(
+            parse_posix_t("202401151000.30.25").unwrap(),
+            local(2024, 1, 15, 10, 0, 30) + Duration::from_millis(250)
+        );
+        assert_eq!(
+            parse_posix_t("202401151000.30.123456789").unwrap(),
+            local(2024, 1, 15, 10, 0, 30) + Duration::from_nanos(123_456_789)
+        );
+        for bad in [
+            "202401151000.30.",
+            "202401151000.30.1234567890",
+            "202401151000.30.x",
+        ] {
+            assert!(parse_posix_t(bad).is_err(), "{:?} was accepted", bad);
+        }
+    }
+
+    #[test]
+    fn posix_t_century_digits_reach_before_1900() {
+        assert_eq!(
+            parse_posix_t("189512312359").unwrap(),
+            local(1895, 12, 31, 23, 59, 0)
+        );
+        let time = chrono::DateTime::<chrono::Local>::from(parse_posix_t("189512312359").unwrap());
+        assert_eq!(time.year(), 1895);
+    }
+
+    #[test]
+    fn date_output_round_trips_through_the_zone_table() {
+        for (zone, instant) in [
+            ("UTC", 1_705_312_800),
+            ("IST", 1_705_312_800),
+            ("PDT", 1_721_037_600),
+            ("NZDT", 1_000_000_000),
+        ] {
+            let offset = FixedOffset::east_opt(zone_offset(zone).unwrap()).unwrap();
+            let shown = chrono::DateTime::from_timestamp(instant, 0)
+                .unwrap()
+                .with_timezone(&offset);
+            let printed = shown
+                .format(&format!("%a %b %e %H:%M:%S {} %Y", zone))
+                .to_string();
+            assert_eq!(
+                parse_date_output(&printed),
+                Some(epoch(instant as u64, 0)),
+                "{:?}",
+                printed
+            );
+        }
+    }
+
+    #[test]
+    fn date_output_needs_a_zone_it_knows() {
+        assert_eq!(parse_date_output("Mon Jan 15 10:00:00 XYZ 2024"), None);
+        assert_eq!(parse_date_output("Mon Jan 15 10:00:00 2024"), None);
+        assert_eq!(zone_offset("CST"), Some(-6 * 3600));
+    }
+
+    #[test]
+    fn two_digit_years_in_d_pivot_like_t() {
+        for (date, stamp) in [
+            ("01/15/69 10:00", "6901151000"),
+            ("01/15/68 10:00", "6801151000"),
+            ("01/15/00 10:00", "0001151000"),
+            ("01/15/2024 10:00", "202401151000"),
+        ] {
+            assert_eq!(
+                parse_date(date).unwrap(),
+                parse_posix_t(stamp).unwrap(),
+                "{:?}",
+                date
+            );
+        }
+        assert_eq!(
+            parse_date("01/15/69 10:00").unwrap(),
+            local(1969, 1, 15, 10, 0, 0)
+        );
+    }
+}
@@ -0,0 +1,30 @@
+//! The touch engine behind the `tick` command, for programs that want to set
+//! file times the way tick does without going through its command line.
+
+use std::io::Write;
+
+pub mod date;
+mod timespec;
+mod touch;
+
+pub use timespec::TimeSpec;
+pub use touch::{
+    apply_times, read_link_times, read_times, resolve_symlinks, target, touch, touch_many, Source,
+    TimeSelection, TouchError, TouchOptions, Touched,
+};
+
+pub const NAME: &str = "tick";
+
+const WARNING_STYLE: anstyle::Style = anstyle::AnsiColor::Yellow.on_default().bold();
+
+/// Report a problem that does not stop tick, coloured as --color allows.
+pub fn warn(message: std::fmt::Arguments) {
+    let _ = writeln!(
+        anstream::stderr(),
+        "{}: {}warning:{} {}",
+        NAME,
+        WARNING_STYLE.render(),
+        WARNING_STYLE.render_reset(),
+        message
+    );
+}
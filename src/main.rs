@@ -1,30 +1,50 @@
 use std::{
-    fs::{self, File, FileTimes},
-    io::Write,
-    path::{self, PathBuf},
+    collections::HashSet,
+    fs::{self, File},
+    io::{BufRead, BufReader, Write},
+    path::{self, Path, PathBuf},
+    process::ExitCode,
     time::SystemTime,
 };
 
 use anyhow::Context;
-use clap::Parser;
+use clap::{
+    error::{ContextKind, ContextValue, ErrorKind},
+    Parser, ValueEnum,
+};
+use tick::{
+    date::{english_meridiem, parse_auto_epoch, parse_date, parse_posix_t},
+    read_link_times, read_times, warn, Source, TimeSelection, TouchError, TouchOptions, Touched,
+    NAME,
+};
 
+/// The words --time accepts.
+#[derive(ValueEnum, Clone, Copy, Debug)]
 enum Word {
     Access,
     Atime,
     Use,
     Modify,
     Mtime,
+    All,
+    Both,
+    None,
+}
+
+/// When to colour tick's own messages.
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum ColorWhen {
+    Auto,
+    Always,
+    Never,
 }
 
-impl From<String> for Word {
-    fn from(word: String) -> Self {
-        match word.to_lowercase().as_str() {
-            "access" => Word::Access,
-            "atime" => Word::Atime,
-            "use" => Word::Use,
-            "modify" => Word::Modify,
-            "mtime" => Word::Mtime,
-            _ => Word::Use,
+impl From<ColorWhen> for anstream::ColorChoice {
+    fn from(when: ColorWhen) -> Self {
+        match when {
+            ColorWhen::Auto => anstream::ColorChoice::Auto,
+            ColorWhen::Always => anstream::ColorChoice::Always,
+            ColorWhen::Never => anstream::ColorChoice::Never,
         }
     }
 }
@@ -32,8 +52,15 @@ impl From<String> for Word {
 #[derive(Parser, Debug)]
 struct Args {
     /// A  FILE argument that does not exist is created empty, unless -c or -h is supplied.
+    /// Use '--' to end option parsing, e.g. 'tick -- -m' touches a file named '-m'.
+    /// Braces are expanded, even when quoted, so 'a{1,2}.txt' names 'a1.txt' and 'a2.txt';
+    /// a FILE after '--' is taken as it is
     files: Option<Vec<path::PathBuf>>,
 
+    /// FILEs after '--', taken as they are without brace expansion
+    #[clap(last = true, value_name = "FILE")]
+    literal_files: Option<Vec<path::PathBuf>>,
+
     /// do not create any files
     #[clap(short = 'c', long)]
     no_create: bool,
@@ -46,15 +73,88 @@ struct Args {
     #[clap(short = 'd', long)]
     date: Option<String>,
 
+    /// like -d, but parse the first non-empty line of FILE
+    #[clap(long, value_name = "FILE", conflicts_with = "date")]
+    date_file: Option<path::PathBuf>,
+
     /// affect each symbolic link instead of any referenced file (useful only
     /// on systems that can change the timestamps of a symlink)
-    #[clap(short = 'n', long)]
+    #[clap(short = 'n', long, overrides_with = "dereference")]
     no_dereference: bool,
 
+    /// follow symbolic links to the files they name (the default); whichever of
+    /// -L and -n comes last wins
+    #[clap(short = 'L', long, overrides_with = "no_dereference")]
+    dereference: bool,
+
     ///change only the modification time
     #[clap(short = 'm')]
     modify_time_only: bool,
 
+    /// with -d, read a bare 10-digit number as epoch seconds and a 13-digit one
+    /// as epoch milliseconds
+    #[clap(long)]
+    auto_epoch: bool,
+
+    /// read -d's AM/PM markers in LOCALE rather than LC_TIME's locale
+    #[clap(long, value_name = "LOCALE")]
+    locale: Option<String>,
+
+    /// also touch the paths listed one per line in LIST, or on stdin if LIST is '-'
+    #[clap(long, value_name = "LIST")]
+    files_from: Option<path::PathBuf>,
+
+    /// separate --files-from entries, and end -v and --print lines, with NUL
+    /// rather than newline, as 'find -print0' does
+    #[clap(short = 'z', long)]
+    null_data: bool,
+
+    /// drop trailing whitespace from each --files-from line (a trailing carriage
+    /// return is always dropped); NUL-separated entries are kept as they are
+    #[clap(long)]
+    strip_trailing_whitespace: bool,
+
+    /// if a FILE cannot be opened for lack of permission, make it readable and
+    /// writable by its owner while its times are set, then restore its mode;
+    /// on Linux, likewise lift an immutable or append-only attribute
+    #[clap(long)]
+    force: bool,
+
+    /// leave the access and modification times alone and only bump the inode
+    /// change time (ctime), which cannot be set directly: the FILE's mtime is
+    /// written back unchanged, a metadata change that moves ctime to now
+    #[clap(
+        long,
+        conflicts_with_all = [
+            "access",
+            "date",
+            "date_file",
+            "modify_time_only",
+            "reference",
+            "time",
+            "word"
+        ]
+    )]
+    touch_ctime: bool,
+
+    /// create any missing parent directories of a FILE before creating it
+    #[clap(short = 'p', long)]
+    parents: bool,
+
+    /// also change the times of everything below each directory FILE
+    #[clap(short = 'R', long)]
+    recursive: bool,
+
+    /// with -R, descend at most N levels below each FILE: 0 touches only the
+    /// FILEs themselves, 1 also their immediate contents, and so on
+    #[clap(long, value_name = "N", requires = "recursive")]
+    max_depth: Option<usize>,
+
+    /// with -R, take each directory's entries in the order the file system
+    /// lists them rather than sorting them by name
+    #[clap(long, requires = "recursive")]
+    unsorted: bool,
+
     ///use this file's times instead of current time
     #[clap(short = 'r', long)]
     reference: Option<path::PathBuf>,
@@ -65,86 +165,693 @@ struct Args {
     #[clap(short = 't')]
     time: Option<String>,
 
+    /// report each FILE touched; repeat (-vv) to also show its old and new times
+    #[clap(short = 'v', long, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// read each FILE's times back after setting them and warn about any the
+    /// file system did not keep exactly, e.g. because it stores coarser times
+    #[clap(long)]
+    verify: bool,
+
+    /// print each FILE's access and modification times after touching it; with
+    /// --time=none, the times an existing FILE already has
+    #[clap(long)]
+    print: bool,
+
+    /// append each FILE's times to LOG as it is touched, in --print's layout
+    #[clap(long, value_name = "LOG")]
+    timestamp_file: Option<path::PathBuf>,
+
+    /// show printed times in UTC rather than the local time zone
+    #[clap(long)]
+    utc: bool,
+
+    /// colour error and warning messages: 'auto' (when stderr is a terminal and
+    /// NO_COLOR is unset), 'always' or 'never'
+    #[clap(long, value_enum, default_value_t = ColorWhen::Auto)]
+    color: ColorWhen,
+
+    /// report a failure on stderr as one JSON object with its path, kind,
+    /// message and errno, instead of as text
+    #[clap(long)]
+    json_errors: bool,
+
+    /// exit with status 3 rather than 1 when a FILE cannot be created because
+    /// a directory leading to it does not exist, without trying the FILEs after it
+    #[clap(long)]
+    exit_missing: bool,
+
     /// specify which time to change:
     ///   access time (-a): 'access', 'atime', 'use';
-    ///   modification time (-m): 'modify', 'mtime'
-    #[clap(long = "time")]
-    word: Option<String>,
+    ///   modification time (-m): 'modify', 'mtime';
+    ///   both: 'all', 'both';
+    ///   neither, only creating missing files: 'none'
+    #[clap(
+        long = "time",
+        value_enum,
+        ignore_case = true,
+        hide_possible_values = true
+    )]
+    word: Option<Word>,
+}
+
+const ERROR_STYLE: anstyle::Style = anstyle::AnsiColor::Red.on_default().bold();
+
+/// The exit status --exit-missing gives a FILE whose directory is missing.
+const EXIT_MISSING: u8 = 3;
+
+/// A mistake in how tick was invoked, reported with exit status 2 rather than 1
+/// and followed by the `hint()` line. A date tick cannot read is one, whether
+/// given with -d or -t or held in a --date-file; a file tick cannot open or
+/// touch is not, since the invocation itself was sound.
+#[derive(Debug)]
+struct UsageError(String);
+
+impl std::fmt::Display for UsageError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
 }
 
-const NAME: &str = "tick";
+impl std::error::Error for UsageError {}
 
-fn main() -> anyhow::Result<()> {
-    let args = Args::parse();
-    if let Some(files) = &args.files {
+/// The line pointing a confused user at --help.
+fn hint() -> String {
+    format!("Try '{} --help' for more information", NAME)
+}
+
+fn main() -> ExitCode {
+    let args = parse_args();
+    anstream::ColorChoice::from(args.color).write_global();
+    match run(&args) {
+        Ok(code) => code,
+        // Whoever was reading the output (say, `head`) has stopped: nothing to report.
+        Err(err) if is_broken_pipe(&err) => ExitCode::SUCCESS,
+        Err(err) => {
+            report_error(&args, &err);
+            if err.is::<UsageError>() {
+                ExitCode::from(2)
+            } else if args.exit_missing && is_missing_path(&err) {
+                ExitCode::from(EXIT_MISSING)
+            } else {
+                ExitCode::FAILURE
+            }
+        }
+    }
+}
+
+/// Report `err` on stderr, as JSON under --json-errors, with the hint after a
+/// usage error.
+fn report_error(args: &Args, err: &anyhow::Error) {
+    if args.json_errors {
+        eprintln!("{}", json_error(err));
+        return;
+    }
+    let _ = writeln!(
+        anstream::stderr(),
+        "{}{}:{} {:#}",
+        ERROR_STYLE.render(),
+        NAME,
+        ERROR_STYLE.render_reset(),
+        err
+    );
+    if err.is::<UsageError>() {
+        eprintln!("{}", hint());
+    }
+}
+
+/// Whether `err` is a write to a pipe whose reader has gone away.
+fn is_broken_pipe(err: &anyhow::Error) -> bool {
+    err.chain()
+        .filter_map(|cause| cause.downcast_ref::<std::io::Error>())
+        .any(|io_error| io_error.kind() == std::io::ErrorKind::BrokenPipe)
+}
+
+/// Whether `err` is a FILE that could not be created or opened because part of
+/// its path does not exist.
+fn is_missing_path(err: &anyhow::Error) -> bool {
+    err.chain()
+        .filter_map(|cause| cause.downcast_ref::<TouchError>())
+        .any(|touch_error| touch_error.io_error().kind() == std::io::ErrorKind::NotFound)
+}
+
+/// Describe `err` for --json-errors. `kind` is "usage" for a usage error and
+/// otherwise names the OS error behind it (e.g. "PermissionDenied"), if any;
+/// `path` and `errno` are null when unknown.
+fn json_error(err: &anyhow::Error) -> String {
+    let touch_error = err
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<TouchError>());
+    let io_error = touch_error.map(TouchError::io_error).or_else(|| {
+        err.chain()
+            .find_map(|cause| cause.downcast_ref::<std::io::Error>())
+    });
+    let kind = match io_error {
+        _ if err.is::<UsageError>() => json_string("usage"),
+        Some(io_error) => json_string(&format!("{:?}", io_error.kind())),
+        None => "null".to_string(),
+    };
+    let path = match touch_error {
+        Some(touch_error) => json_string(&touch_error.path().to_string_lossy()),
+        None => "null".to_string(),
+    };
+    let errno = match io_error.and_then(std::io::Error::raw_os_error) {
+        Some(errno) => errno.to_string(),
+        None => "null".to_string(),
+    };
+    format!(
+        "{{\"path\":{},\"kind\":{},\"message\":{},\"errno\":{}}}",
+        path,
+        kind,
+        json_string(&format!("{:#}", err)),
+        errno
+    )
+}
+
+/// Quote `text` as a JSON string.
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Touch every FILE, returning the exit status once each has been tried. An
+/// error that stops tick early comes back as `Err`.
+fn run(args: &Args) -> anyhow::Result<ExitCode> {
+    if args.files.is_none() && args.literal_files.is_none() && args.files_from.is_none() {
+        return Err(UsageError("missing operand".to_string()).into());
+    }
+    let opts = options(args, source(args)?);
+    let mut files: Vec<PathBuf> = args
+        .files
+        .iter()
+        .flatten()
+        .flat_map(|file| expand_path(file))
+        .chain(args.literal_files.iter().flatten().cloned())
+        .collect();
+    if let Some(list) = &args.files_from {
+        files.extend(read_files_from(args, list)?);
+    }
+    let log = match &args.timestamp_file {
+        Some(path) => Some(
+            fs::OpenOptions::new()
+                .append(true)
+                .create(true)
+                .open(path)
+                .with_context(|| format!("opening timestamp file {:?}", path))?,
+        ),
+        None => None,
+    };
+    // Like touch, carry on past a FILE that fails and exit 1 at the end, unless
+    // --exit-missing asks to stop at the first missing directory.
+    let mut failed = false;
+    let mut fail = |err: anyhow::Error| {
+        if is_broken_pipe(&err) || (args.exit_missing && is_missing_path(&err)) {
+            return Err(err);
+        }
+        report_error(args, &err);
+        failed = true;
+        Ok(())
+    };
+    // With nothing to walk, report or check per FILE, the library's bulk call
+    // does the lot.
+    let per_file = args.recursive || args.verbose > 0 || args.print || args.verify;
+    if !per_file && log.is_none() && !args.exit_missing {
+        for (_, result) in tick::touch_many(files, &opts) {
+            result.or_else(&mut fail)?;
+        }
+    } else {
         for file in files {
-            let metadata = fs::metadata(&file);
-            match metadata {
-                Ok(_) => {
-                    tick(&args, &file)?;
-                }
-                Err(_) => {
-                    if !args.no_create {
-                        std::fs::File::create(&file)?;
+            if args.recursive {
+                touch_tree(args, &opts, log.as_ref(), &file)
+            } else {
+                touch_path(args, &opts, log.as_ref(), &file)
+            }
+            .or_else(&mut fail)?;
+        }
+    }
+    Ok(if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+/// The first non-empty line of the --date-file FILE, trimmed.
+fn read_date_file(file: &Path) -> anyhow::Result<String> {
+    let reader = File::open(file).with_context(|| format!("opening date file {:?}", file))?;
+    for line in BufReader::new(reader).lines() {
+        let line = line.with_context(|| format!("reading date file {:?}", file))?;
+        if !line.trim().is_empty() {
+            return Ok(line.trim().to_string());
+        }
+    }
+    Err(UsageError(format!("date file {:?} holds no date", file)).into())
+}
+
+/// Read the --files-from list, from stdin when it is '-'. Entries end with a
+/// newline, or with a NUL under -z.
+fn read_files_from(args: &Args, list: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let delimiter = if args.null_data { b'\0' } else { b'\n' };
+    let strip = args.strip_trailing_whitespace;
+    if list == Path::new("-") {
+        read_path_list(std::io::stdin().lock(), delimiter, strip)
+            .context("reading file list from standard input")
+    } else {
+        let file = File::open(list).with_context(|| format!("opening file list {:?}", list))?;
+        read_path_list(BufReader::new(file), delimiter, strip)
+            .with_context(|| format!("reading file list {:?}", list))
+    }
+}
+
+/// Split a list of paths on `delimiter`, skipping empty entries. Lines split at
+/// newlines lose a trailing '\r', as CRLF files end them, and under
+/// `strip_whitespace` any other trailing whitespace; NUL-split entries are kept
+/// byte for byte.
+fn read_path_list(
+    reader: impl BufRead,
+    delimiter: u8,
+    strip_whitespace: bool,
+) -> anyhow::Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    for entry in reader.split(delimiter) {
+        let mut entry = entry?;
+        if delimiter == b'\n' {
+            if entry.last() == Some(&b'\r') {
+                entry.pop();
+            }
+            while strip_whitespace && entry.last().is_some_and(u8::is_ascii_whitespace) {
+                entry.pop();
+            }
+        }
+        if !entry.is_empty() {
+            paths.push(path_from_bytes(entry)?);
+        }
+    }
+    Ok(paths)
+}
+
+#[cfg(unix)]
+fn path_from_bytes(bytes: Vec<u8>) -> anyhow::Result<PathBuf> {
+    use std::os::unix::ffi::OsStringExt;
+
+    Ok(PathBuf::from(std::ffi::OsString::from_vec(bytes)))
+}
+
+#[cfg(not(unix))]
+fn path_from_bytes(bytes: Vec<u8>) -> anyhow::Result<PathBuf> {
+    Ok(PathBuf::from(
+        String::from_utf8(bytes).context("file list entry is not valid UTF-8")?,
+    ))
+}
+
+/// Expand shell-style braces in a FILE argument ('a{1,2}.txt' names 'a1.txt'
+/// and 'a2.txt'), so the same command line works where the shell does not.
+fn expand_path(file: &Path) -> Vec<PathBuf> {
+    match file.to_str() {
+        Some(pattern) if pattern.contains('{') => expand_braces(pattern)
+            .into_iter()
+            .map(PathBuf::from)
+            .collect(),
+        _ => vec![file.to_path_buf()],
+    }
+}
+
+/// Expand the first brace group holding a top-level comma, then whatever groups
+/// remain in each result. Groups without a comma, like '{}', stay literal.
+fn expand_braces(pattern: &str) -> Vec<String> {
+    for (open, _) in pattern.match_indices('{') {
+        let mut depth = 0;
+        let mut commas = Vec::new();
+        for (i, c) in pattern[open..].char_indices().skip(1) {
+            match c {
+                '{' => depth += 1,
+                '}' if depth > 0 => depth -= 1,
+                ',' if depth == 0 => commas.push(open + i),
+                '}' => {
+                    if commas.is_empty() {
+                        break;
                     }
+                    let close = open + i;
+                    let (prefix, suffix) = (&pattern[..open], &pattern[close + 1..]);
+                    let mut bounds = vec![open];
+                    bounds.extend(&commas);
+                    bounds.push(close);
+                    return bounds
+                        .windows(2)
+                        .flat_map(|pair| {
+                            let alternative = &pattern[pair[0] + 1..pair[1]];
+                            expand_braces(&format!("{}{}{}", prefix, alternative, suffix))
+                        })
+                        .collect();
                 }
+                _ => {}
             }
         }
-    } else {
-        let stdout = std::io::stdout();
-        let mut handle = stdout.lock();
-        handle.write(
-            format!(
-                "{}: missing operand\nTry '{} --help' for more information\n",
-                NAME, NAME
-            )
-            .as_bytes(),
-        )?;
+    }
+    vec![pattern.to_string()]
+}
+
+/// Touch one path and report it as -v, --print and --timestamp-file ask.
+fn touch_path(
+    args: &Args,
+    opts: &TouchOptions,
+    log: Option<&File>,
+    file: &Path,
+) -> anyhow::Result<()> {
+    let target = tick::target(file, opts)?;
+    let mut before = None;
+    if args.verbose > 1 {
+        before = read_target_times(args, &target).ok();
+    }
+    let touched = tick::touch(file, opts)?;
+    if touched == Touched::Skipped {
+        // --time=none leaves an existing FILE as it is, so --print shows its
+        // times without changing them.
+        if args.print && opts.times.is_empty() && target.exists() {
+            let line = times_line(args, file, &target)?;
+            emit(args, format_args!("{}", line)).context("writing to standard output")?;
+        }
+        return Ok(());
+    }
+    if args.verify {
+        verify_times(args, opts, file, &target)?;
+    }
+    if args.verbose > 0 {
+        report_touch(args, file, &target, touched, before)?;
+    }
+    if args.print || log.is_some() {
+        let line = times_line(args, file, &target)?;
+        if args.print {
+            emit(args, format_args!("{}", line)).context("writing to standard output")?;
+        }
+        if let Some(mut log) = log {
+            log.write_all(format!("{}\n", line).as_bytes())
+                .context("writing to the timestamp file")?;
+        }
     }
     Ok(())
 }
 
-fn set_access(file_obj: &File, time: &Source) {
-    let source_time: SystemTime = match time {
-        Source::Single(t) => t.clone(),
-        Source::Multi(t, _) => t.clone(),
-    };
-    let filetimes = FileTimes::new().set_accessed(source_time);
-    let _ = file_obj.set_times(filetimes);
+/// Warn, for --verify, about each time `target` did not keep as it was set.
+fn verify_times(
+    args: &Args,
+    opts: &TouchOptions,
+    file: &Path,
+    target: &Path,
+) -> anyhow::Result<()> {
+    // The kernel picks the time for Source::Now, so there is none to compare.
+    if opts.source == Source::Now {
+        return Ok(());
+    }
+    let read_back = read_target_times(args, target)?;
+    for (name, wanted, kept) in lost_times(opts, read_back) {
+        warn(format_args!(
+            "{:?}: {} was set to {} but reads back as {}, probably rounded by the file system",
+            file,
+            name,
+            format_time(wanted, args.utc),
+            format_time(kept, args.utc)
+        ));
+    }
+    Ok(())
 }
 
-fn set_modified(file_obj: &File, time: &Source) {
-    let source_time: SystemTime = match time {
-        Source::Single(t) => t.clone(),
-        Source::Multi(_, t) => t.clone(),
+/// The times `opts` set that read back as something else, by name, with the
+/// time set and the time kept.
+fn lost_times(
+    opts: &TouchOptions,
+    (atime, mtime): (SystemTime, SystemTime),
+) -> Vec<(&'static str, SystemTime, SystemTime)> {
+    let checks = [
+        ("atime", opts.sets_atime(), opts.source.atime(), atime),
+        ("mtime", opts.sets_mtime(), opts.source.mtime(), mtime),
+    ];
+    checks
+        .into_iter()
+        .filter(|&(_, set, wanted, kept)| set && kept != wanted)
+        .map(|(name, _, wanted, kept)| (name, wanted, kept))
+        .collect()
+}
+
+/// Say for -v whether FILE was created or updated; -vv adds its times, with the
+/// ones it had `before` when it already existed.
+fn report_touch(
+    args: &Args,
+    file: &Path,
+    target: &Path,
+    touched: Touched,
+    before: Option<(SystemTime, SystemTime)>,
+) -> anyhow::Result<()> {
+    let verb = match touched {
+        Touched::Created => "created",
+        Touched::Updated | Touched::Skipped => "updated",
+    };
+    if args.verbose < 2 {
+        return emit(args, format_args!("{} '{}'", verb, file.display()))
+            .context("writing to standard output");
+    }
+    let show = |time| format_time(time, args.utc);
+    let (atime, mtime) = read_target_times(args, target)?;
+    let written = match before {
+        Some((old_atime, old_mtime)) => emit(
+            args,
+            format_args!(
+                "{} '{}': atime {} -> {}, mtime {} -> {}",
+                verb,
+                file.display(),
+                show(old_atime),
+                show(atime),
+                show(old_mtime),
+                show(mtime)
+            ),
+        ),
+        None => emit(
+            args,
+            format_args!(
+                "{} '{}': atime {}, mtime {}",
+                verb,
+                file.display(),
+                show(atime),
+                show(mtime)
+            ),
+        ),
     };
-    let filetimes = FileTimes::new().set_modified(source_time);
-    let _ = file_obj.set_times(filetimes);
+    written.context("writing to standard output")
+}
+
+/// Write one line of -v or --print output, ended by a NUL under -z.
+fn emit(args: &Args, line: std::fmt::Arguments) -> std::io::Result<()> {
+    let end = if args.null_data { '\0' } else { '\n' };
+    write!(std::io::stdout().lock(), "{}{}", line, end)
 }
 
-enum Source {
-    Single(SystemTime),
-    Multi(SystemTime, SystemTime),
+/// The times `target` now has as `ATIME MTIME FILE`, for --print and
+/// --timestamp-file.
+fn times_line(args: &Args, file: &Path, target: &Path) -> anyhow::Result<String> {
+    let (atime, mtime) = read_target_times(args, target)?;
+    Ok(format!(
+        "{} {} {}",
+        format_time(atime, args.utc),
+        format_time(mtime, args.utc),
+        file.display()
+    ))
+}
+
+/// The times `target` has now: a symlink's own under -n, which stamps the link.
+fn read_target_times(args: &Args, target: &Path) -> anyhow::Result<(SystemTime, SystemTime)> {
+    if args.no_dereference {
+        read_link_times(target)
+    } else {
+        read_times(target)
+    }
 }
 
-fn tick(args: &Args, file_path: &PathBuf) -> anyhow::Result<()> {
-    let file_obj =
-        fs::File::open(file_path).with_context(|| format!("opening file {:?}", file_path))?;
+/// Render a time as ISO 8601 with nanoseconds, in UTC under --utc and in the
+/// local time zone otherwise.
+fn format_time(time: SystemTime, utc: bool) -> String {
+    const ISO_8601: &str = "%Y-%m-%dT%H:%M:%S%.9f%:z";
+    let time = chrono::DateTime::<chrono::Utc>::from(time);
+    if utc {
+        time.format(ISO_8601).to_string()
+    } else {
+        time.with_timezone(&chrono::Local)
+            .format(ISO_8601)
+            .to_string()
+    }
+}
 
-    let src: Source = match (&args.date, &args.time, &args.reference) {
+/// Stamp `root` and, for -R, everything below it down to --max-depth, taking
+/// each directory's entries in byte order of their names unless --unsorted.
+/// Symlinked directories are followed unless -n is given, so each directory's
+/// (device, inode) is remembered and a directory seen twice is reported as a
+/// cycle instead of being walked again.
+fn touch_tree(
+    args: &Args,
+    opts: &TouchOptions,
+    log: Option<&File>,
+    root: &Path,
+) -> anyhow::Result<()> {
+    let mut visited = HashSet::new();
+    // Each pending path comes with its depth and whether it may be a directory
+    // to walk, which a plain file or an unfollowed symlink is not.
+    let mut pending = vec![(root.to_path_buf(), 0, true)];
+    while let Some((path, depth, walk)) = pending.pop() {
+        let meta = if args.no_dereference {
+            fs::symlink_metadata(&path)
+        } else {
+            fs::metadata(&path)
+        };
+        let meta = match meta {
+            Ok(meta) if walk && meta.is_dir() => meta,
+            _ => {
+                touch_path(args, opts, log, &path)?;
+                continue;
+            }
+        };
+        if args.max_depth.is_some_and(|max_depth| depth >= max_depth) {
+            touch_path(args, opts, log, &path)?;
+            continue;
+        }
+        if let Some(id) = file_id(&meta) {
+            if !visited.insert(id) {
+                warn(format_args!(
+                    "{:?}: directory already visited, not descending again",
+                    path
+                ));
+                continue;
+            }
+        }
+        // List the directory before stamping it: reading it afterwards could
+        // bump the access time just set.
+        let mut entries = Vec::new();
+        for entry in fs::read_dir(&path).with_context(|| format!("reading directory {:?}", path))? {
+            let entry = entry.with_context(|| format!("reading directory {:?}", path))?;
+            entries.push((entry.path(), entry.file_type()?));
+        }
+        if !args.unsorted {
+            entries.sort_unstable_by(|(one, _), (other, _)| one.cmp(other));
+        }
+        touch_path(args, opts, log, &path)?;
+        // Pushed in reverse, so the entries come off the stack in order.
+        for (entry, file_type) in entries.into_iter().rev() {
+            let walk = file_type.is_dir() || (file_type.is_symlink() && !args.no_dereference);
+            pending.push((entry, depth + 1, walk));
+        }
+    }
+    Ok(())
+}
+
+/// The (device, inode) pair identifying a file, where the platform has one.
+#[cfg(unix)]
+fn file_id(meta: &fs::Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+
+    Some((meta.dev(), meta.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(_meta: &fs::Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+/// Parse the command line, pointing out '--' when a dash-leading filename was
+/// mistaken for a cluster of short flags (e.g. '-foo.txt' is reported as '-f').
+/// Under --json-errors a usage error is reported as a JSON object, like any
+/// other error, though clap could not finish parsing the flag itself.
+fn parse_args() -> Args {
+    Args::try_parse().unwrap_or_else(|mut err| {
+        if err.kind() == ErrorKind::UnknownArgument {
+            if let Some(word) = dash_leading_word(&err) {
+                err.insert(ContextKind::InvalidArg, ContextValue::String(word.clone()));
+                err.insert(
+                    ContextKind::Suggested,
+                    ContextValue::StyledStrs(vec![format!(
+                        "to touch a file named '{}', use '{} -- {}'",
+                        word, NAME, word
+                    )
+                    .into()]),
+                );
+            }
+        }
+        if err.use_stderr() && json_errors_requested() {
+            let rendered = err.render().to_string();
+            let message = rendered.lines().next().unwrap_or_default();
+            let message = message.strip_prefix("error: ").unwrap_or(message);
+            eprintln!("{}", json_error(&UsageError(message.to_string()).into()));
+            std::process::exit(err.exit_code());
+        }
+        err.exit()
+    })
+}
+
+/// Whether --json-errors is among the flags, for errors found before the rest
+/// of the command line could be parsed.
+fn json_errors_requested() -> bool {
+    std::env::args_os()
+        .skip(1)
+        .take_while(|arg| arg != "--")
+        .any(|arg| arg == "--json-errors")
+}
+
+/// Find the whole command line word an unknown short flag came from.
+fn dash_leading_word(err: &clap::Error) -> Option<String> {
+    let Some(ContextValue::String(invalid)) = err.get(ContextKind::InvalidArg) else {
+        return None;
+    };
+    let flag = invalid.strip_prefix('-').filter(|f| !f.starts_with('-'))?;
+    // A word that is not UTF-8 cannot be the one clap quoted, so skip it.
+    std::env::args_os()
+        .skip(1)
+        .take_while(|arg| arg != "--")
+        .filter_map(|arg| arg.into_string().ok())
+        .find(|arg| {
+            arg.len() > invalid.len()
+                && arg.starts_with('-')
+                && !arg.starts_with("--")
+                && arg[1..].contains(flag)
+        })
+}
+
+fn source(args: &Args) -> anyhow::Result<Source> {
+    let date = match &args.date_file {
+        Some(file) => Some(read_date_file(file)?),
+        None => args.date.clone(),
+    };
+    let src: Source = match (&date, &args.time, &args.reference) {
         (Some(date), None, None) => Source::Single(
-            dateparser::parse(&date)
-                .with_context(|| format!("parsing date string {:?}", &date))?
-                .into(),
+            match parse_auto_epoch(date).filter(|_| args.auto_epoch) {
+                Some(time) => time,
+                None => english_meridiem(date, args.locale.as_deref())
+                    .and_then(|date| parse_date(&date)),
+            }
+            .context(UsageError(format!("parsing date string {:?}", &date)))?,
         ),
 
         (None, Some(time), None) => Source::Single(
-            dateparser::parse(&time)
-                .with_context(|| format!("parsing time string {:?}", &time))?
-                .into(),
+            parse_posix_t(time).context(UsageError(format!("parsing time string {:?}", &time)))?,
         ),
         (None, None, Some(reference)) => {
-            let ref_meta = fs::metadata(reference)?;
+            // Only stat the reference: opening it could bump the atime being copied.
+            // Under -n a symlinked reference lends its own times.
+            let ref_meta = if args.no_dereference {
+                fs::symlink_metadata(reference)
+            } else {
+                fs::metadata(reference)
+            }
+            .with_context(|| format!("reading reference {:?}", reference))?;
             let atime = ref_meta
                 .accessed()
                 .with_context(|| format!("getting accessed time {:?}", reference))?;
@@ -153,43 +860,148 @@ fn tick(args: &Args, file_path: &PathBuf) -> anyhow::Result<()> {
                 .with_context(|| format!("getting modified time {:?}", reference))?;
             Source::Multi(atime, mtime)
         }
-        (None, None, None) => Source::Single(SystemTime::now()),
-        _ => anyhow::bail!("Cannot use -t, -d, or -r at the same time"),
+        (None, None, None) => Source::Now,
+        _ => {
+            return Err(UsageError("Cannot use -t, -d, or -r at the same time".to_string()).into())
+        }
     };
+    Ok(src)
+}
 
-    let filetimes = FileTimes::new();
-    match (&args.access, &args.modify_time_only, &args.word) {
-        (true, true, _) => {
-            set_access(&file_obj, &src);
-            set_modified(&file_obj, &src);
-        }
-        (true, false, None) => set_access(&file_obj, &src),
-        (_, false, Some(w)) => {
-            on_time(w, src, &file_obj);
-        }
-        (false, true, None) => set_modified(&file_obj, &src),
-        (false, true, Some(w)) => {
-            set_modified(&file_obj, &src);
-            on_time(w, src, &file_obj);
-        }
-        (false, false, None) => {
-            set_modified(&file_obj, &src);
-            set_access(&file_obj, &src);
-        }
+/// The touch options the flags ask for, with -a, -m and --time merged into the
+/// times to set.
+fn options(args: &Args, source: Source) -> TouchOptions {
+    TouchOptions {
+        source,
+        times: selection(args, args.word),
+        no_create: args.no_create,
+        no_dereference: args.no_dereference,
+        parents: args.parents,
+        force: args.force,
+        ctime_only: args.touch_ctime,
     }
+}
 
-    let _ = file_obj.set_times(filetimes);
-
-    Ok(())
+/// The times -a, -m and --time select between them; none of them at all
+/// selects both, and --time=none selects neither.
+fn selection(args: &Args, word: Option<Word>) -> TimeSelection {
+    let mut times = TimeSelection::empty();
+    match word {
+        Some(Word::None) => return times,
+        Some(Word::Access | Word::Atime | Word::Use) => times |= TimeSelection::ACCESS,
+        Some(Word::Modify | Word::Mtime) => times |= TimeSelection::MODIFY,
+        Some(Word::All | Word::Both) => times |= TimeSelection::ACCESS | TimeSelection::MODIFY,
+        None => {}
+    }
+    if args.access {
+        times |= TimeSelection::ACCESS;
+    }
+    if args.modify_time_only {
+        times |= TimeSelection::MODIFY;
+    }
+    if times.is_empty() {
+        times = TimeSelection::ACCESS | TimeSelection::MODIFY;
+    }
+    times
 }
 
-fn on_time(word: &str, src: Source, file_obj: &File) {
-    let word_kind = Word::from(word.to_string());
-    match word_kind {
-        Word::Use => set_access(&file_obj, &src),
-        Word::Access => set_access(&file_obj, &src),
-        Word::Atime => set_access(&file_obj, &src),
-        Word::Modify => set_modified(&file_obj, &src),
-        Word::Mtime => set_modified(&file_obj, &src),
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn braces_expand_each_alternative_in_order() {
+        assert_eq!(expand_braces("a{1,2}.txt"), ["a1.txt", "a2.txt"]);
+        assert_eq!(expand_braces("{x,y}{1,2}"), ["x1", "x2", "y1", "y2"]);
+        assert_eq!(expand_braces("a{b,{c,d}}e"), ["abe", "ace", "ade"]);
+        assert_eq!(expand_braces("a{,b}"), ["a", "ab"]);
+    }
+
+    #[test]
+    fn braces_without_a_comma_stay_literal() {
+        assert_eq!(expand_braces("a{}b"), ["a{}b"]);
+        assert_eq!(expand_braces("a{b}c"), ["a{b}c"]);
+        assert_eq!(expand_braces("a{b,c"), ["a{b,c"]);
+        assert_eq!(expand_braces("{b}{1,2}"), ["{b}1", "{b}2"]);
+    }
+
+    fn paths(list: &[u8], delimiter: u8, strip_whitespace: bool) -> Vec<PathBuf> {
+        read_path_list(list, delimiter, strip_whitespace).unwrap()
+    }
+
+    #[test]
+    fn a_path_list_skips_empty_lines() {
+        assert_eq!(
+            paths(b"one\n\ntwo words\nlast", b'\n', false),
+            [
+                PathBuf::from("one"),
+                PathBuf::from("two words"),
+                PathBuf::from("last")
+            ]
+        );
+        assert!(paths(b"", b'\n', false).is_empty());
+    }
+
+    #[test]
+    fn json_strings_escape_quotes_backslashes_and_control_characters() {
+        assert_eq!(json_string("plain"), "\"plain\"");
+        assert_eq!(json_string("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(json_string("C:\\dir"), "\"C:\\\\dir\"");
+        assert_eq!(
+            json_string("a\nb\tc\r\u{1b}"),
+            "\"a\\nb\\tc\\u000d\\u001b\""
+        );
+        assert_eq!(json_string("naïve"), "\"naïve\"");
+    }
+
+    #[test]
+    fn only_times_a_coarse_file_system_rounded_are_lost() {
+        let set = SystemTime::UNIX_EPOCH + std::time::Duration::from_millis(1_000_000_000_500);
+        let whole = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000_000);
+        let mut opts = TouchOptions {
+            source: Source::from(set),
+            ..Default::default()
+        };
+        assert_eq!(
+            lost_times(&opts, (whole, whole)),
+            [("atime", set, whole), ("mtime", set, whole)]
+        );
+        assert!(lost_times(&opts, (set, set)).is_empty());
+
+        opts.times = TimeSelection::ACCESS;
+        assert_eq!(lost_times(&opts, (whole, whole)), [("atime", set, whole)]);
+    }
+
+    #[test]
+    fn nul_separated_entries_are_kept_byte_for_byte() {
+        assert_eq!(
+            paths(b"a\0b\nc \0\0d", b'\0', true),
+            [
+                PathBuf::from("a"),
+                PathBuf::from("b\nc "),
+                PathBuf::from("d")
+            ]
+        );
+    }
+
+    #[test]
+    fn crlf_endings_go_and_other_trailing_whitespace_only_when_asked() {
+        let list = b"one\r\ntwo \t\r\n  three\n";
+        assert_eq!(
+            paths(list, b'\n', false),
+            [
+                PathBuf::from("one"),
+                PathBuf::from("two \t"),
+                PathBuf::from("  three")
+            ]
+        );
+        assert_eq!(
+            paths(list, b'\n', true),
+            [
+                PathBuf::from("one"),
+                PathBuf::from("two"),
+                PathBuf::from("  three")
+            ]
+        );
     }
 }
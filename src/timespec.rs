@@ -0,0 +1,155 @@
+use std::time::{Duration, SystemTime};
+
+const NANOS_PER_SEC: u32 = 1_000_000_000;
+
+/// A file time as whole seconds since the Unix epoch plus nanoseconds, or a
+/// marker for a time that should be left as it is or set to the current time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeSpec {
+    /// Leave this time unchanged.
+    Omit,
+    /// The current time, as the kernel reads it when the time is written. Unlike
+    /// an explicit time, this only needs write access to the file, not ownership.
+    Now,
+    /// `secs` since the epoch (negative before 1970) plus `nanos`, which is
+    /// always below one second and counts forward from `secs`.
+    At { secs: i64, nanos: u32 },
+}
+
+impl TimeSpec {
+    /// The `SystemTime` this names, or `None` for `Omit` or a time the platform
+    /// cannot represent. `Now` reads the clock.
+    pub fn to_system_time(self) -> Option<SystemTime> {
+        let (secs, nanos) = match self {
+            TimeSpec::Omit => return None,
+            TimeSpec::Now => return Some(SystemTime::now()),
+            TimeSpec::At { secs, nanos } => (secs, nanos),
+        };
+        let whole = Duration::from_secs(secs.unsigned_abs());
+        let time = if secs < 0 {
+            SystemTime::UNIX_EPOCH.checked_sub(whole)?
+        } else {
+            SystemTime::UNIX_EPOCH.checked_add(whole)?
+        };
+        time.checked_add(Duration::from_nanos(nanos.into()))
+    }
+
+    /// The `timespec` futimens/utimensat expect, using `UTIME_OMIT` for `Omit`
+    /// and `UTIME_NOW` for `Now`.
+    #[cfg(unix)]
+    pub fn to_timespec(self) -> libc::timespec {
+        match self {
+            TimeSpec::Omit => libc::timespec {
+                tv_sec: 0,
+                tv_nsec: libc::UTIME_OMIT,
+            },
+            TimeSpec::Now => libc::timespec {
+                tv_sec: 0,
+                tv_nsec: libc::UTIME_NOW,
+            },
+            TimeSpec::At { secs, nanos } => libc::timespec {
+                tv_sec: secs as libc::time_t,
+                tv_nsec: nanos as _,
+            },
+        }
+    }
+}
+
+impl From<SystemTime> for TimeSpec {
+    fn from(time: SystemTime) -> Self {
+        match time.duration_since(SystemTime::UNIX_EPOCH) {
+            Ok(after) => TimeSpec::At {
+                secs: after.as_secs() as i64,
+                nanos: after.subsec_nanos(),
+            },
+            Err(before) => {
+                let before = before.duration();
+                let secs = -(before.as_secs() as i64);
+                match before.subsec_nanos() {
+                    0 => TimeSpec::At { secs, nanos: 0 },
+                    nanos => TimeSpec::At {
+                        secs: secs - 1,
+                        nanos: NANOS_PER_SEC - nanos,
+                    },
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(time: SystemTime) -> Option<SystemTime> {
+        TimeSpec::from(time).to_system_time()
+    }
+
+    #[test]
+    fn times_after_the_epoch_keep_their_nanoseconds() {
+        let time = SystemTime::UNIX_EPOCH + Duration::new(1_000_000_000, 123_456_789);
+        assert_eq!(
+            TimeSpec::from(time),
+            TimeSpec::At {
+                secs: 1_000_000_000,
+                nanos: 123_456_789
+            }
+        );
+        assert_eq!(round_trip(time), Some(time));
+    }
+
+    #[test]
+    fn sub_second_times_before_the_epoch_count_forward_from_the_second_below() {
+        let time = SystemTime::UNIX_EPOCH - Duration::from_millis(1250);
+        assert_eq!(
+            TimeSpec::from(time),
+            TimeSpec::At {
+                secs: -2,
+                nanos: 750_000_000
+            }
+        );
+        assert_eq!(round_trip(time), Some(time));
+
+        let time = SystemTime::UNIX_EPOCH - Duration::from_nanos(1);
+        assert_eq!(
+            TimeSpec::from(time),
+            TimeSpec::At {
+                secs: -1,
+                nanos: NANOS_PER_SEC - 1
+            }
+        );
+        assert_eq!(round_trip(time), Some(time));
+    }
+
+    #[test]
+    fn whole_seconds_before_the_epoch_have_no_nanoseconds() {
+        let time = SystemTime::UNIX_EPOCH - Duration::from_secs(86_400);
+        assert_eq!(
+            TimeSpec::from(time),
+            TimeSpec::At {
+                secs: -86_400,
+                nanos: 0
+            }
+        );
+        assert_eq!(round_trip(time), Some(time));
+    }
+
+    #[test]
+    fn markers_name_no_fixed_time() {
+        assert_eq!(TimeSpec::Omit.to_system_time(), None);
+        assert!(TimeSpec::Now.to_system_time().is_some());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn markers_become_utime_constants() {
+        assert_eq!(TimeSpec::Omit.to_timespec().tv_nsec, libc::UTIME_OMIT);
+        assert_eq!(TimeSpec::Now.to_timespec().tv_nsec, libc::UTIME_NOW);
+        let spec = TimeSpec::At {
+            secs: -2,
+            nanos: 750_000_000,
+        }
+        .to_timespec();
+        assert_eq!((spec.tv_sec, spec.tv_nsec), (-2, 750_000_000));
+    }
+}
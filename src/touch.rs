@@ -0,0 +1,894 @@
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::Context;
+
+use crate::{warn, TimeSpec};
+
+/// How many symbolic links may be followed while resolving one path, matching
+/// Linux's SYMLOOP_MAX.
+const SYMLOOP_MAX: usize = 40;
+
+/// Where the new times come from. `Multi` carries a reference file's atime and
+/// mtime; a `TimeSelection` only picks which of them is written, so under
+/// `-a -r ref` the atime comes from `ref` and the mtime is left as it was.
+/// `Now` leaves the clock to the kernel, which lets tick touch a file it can
+/// write but does not own, as plain `touch` can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Now,
+    Single(SystemTime),
+    Multi(SystemTime, SystemTime),
+}
+
+/// A set of file times to write, combined with `|`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TimeSelection(u8);
+
+impl TimeSelection {
+    /// The access time (-a).
+    pub const ACCESS: Self = TimeSelection(1);
+    /// The modification time (-m).
+    pub const MODIFY: Self = TimeSelection(2);
+    /// The creation time, which only some platforms let be set.
+    pub const BIRTH: Self = TimeSelection(4);
+
+    /// No times at all.
+    pub const fn empty() -> Self {
+        TimeSelection(0)
+    }
+
+    /// Whether no time is selected.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether every time in `other` is selected.
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for TimeSelection {
+    type Output = Self;
+
+    fn bitor(self, other: Self) -> Self {
+        TimeSelection(self.0 | other.0)
+    }
+}
+
+impl std::ops::BitOrAssign for TimeSelection {
+    fn bitor_assign(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+}
+
+/// What to do to each path, as tick's flags would say it.
+#[derive(Debug, Clone)]
+pub struct TouchOptions {
+    pub source: Source,
+    /// The times to set. When empty, missing files are still created but
+    /// existing ones are left alone (--time=none).
+    pub times: TimeSelection,
+    /// Leave missing files alone instead of creating them (-c).
+    pub no_create: bool,
+    /// Touch a symlink itself rather than what it points at (-n): a link counts
+    /// as existing even when dangling, and its own times are the ones set.
+    pub no_dereference: bool,
+    /// Create missing parent directories (-p).
+    pub parents: bool,
+    /// Work around read-only permissions and immutable attributes (--force).
+    pub force: bool,
+    /// Leave both times as they are and only bump the inode change time, by
+    /// writing back the mtime the file already has (--touch-ctime). Neither
+    /// times nor `source` are used.
+    pub ctime_only: bool,
+}
+
+impl Source {
+    /// The access time to set; for `Now`, the current time.
+    pub fn atime(&self) -> SystemTime {
+        match self {
+            Source::Now => SystemTime::now(),
+            Source::Single(time) | Source::Multi(time, _) => *time,
+        }
+    }
+
+    /// The modification time to set; for `Now`, the current time.
+    pub fn mtime(&self) -> SystemTime {
+        match self {
+            Source::Now => SystemTime::now(),
+            Source::Single(time) | Source::Multi(_, time) => *time,
+        }
+    }
+}
+
+impl From<SystemTime> for Source {
+    fn from(time: SystemTime) -> Self {
+        Source::Single(time)
+    }
+}
+
+/// An (atime, mtime) pair, as read from a reference file.
+impl From<(SystemTime, SystemTime)> for Source {
+    fn from((atime, mtime): (SystemTime, SystemTime)) -> Self {
+        Source::Multi(atime, mtime)
+    }
+}
+
+impl TouchOptions {
+    /// Whether touching sets the access time to the source's.
+    pub fn sets_atime(&self) -> bool {
+        !self.ctime_only && self.times.contains(TimeSelection::ACCESS)
+    }
+
+    /// Whether touching sets the modification time to the source's.
+    pub fn sets_mtime(&self) -> bool {
+        !self.ctime_only && self.times.contains(TimeSelection::MODIFY)
+    }
+}
+
+impl Default for TouchOptions {
+    /// Set both times to the current time, creating missing files.
+    fn default() -> Self {
+        TouchOptions {
+            source: Source::Now,
+            times: TimeSelection::ACCESS | TimeSelection::MODIFY,
+            no_create: false,
+            no_dereference: false,
+            parents: false,
+            force: false,
+            ctime_only: false,
+        }
+    }
+}
+
+/// What `touch` did to a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Touched {
+    /// The file did not exist and was created.
+    Created,
+    /// The file existed and its times were set.
+    Updated,
+    /// Nothing was done: the file did not exist and `no_create` left it so, or
+    /// it did and an empty `times` left it alone.
+    Skipped,
+}
+
+/// The path `touch` would stamp for `path`: `path` itself under
+/// `no_dereference`, otherwise whatever its chain of symlinks ends at.
+pub fn target(path: &Path, opts: &TouchOptions) -> anyhow::Result<PathBuf> {
+    if opts.no_dereference {
+        Ok(path.to_path_buf())
+    } else {
+        resolve_symlinks(path)
+    }
+}
+
+/// A failure to open, create or stamp a path, keeping the path and the OS error
+/// behind it so callers can tell, say, a permission problem from a full disk.
+#[derive(Debug)]
+pub struct TouchError {
+    path: PathBuf,
+    message: String,
+    io_error: std::io::Error,
+}
+
+impl TouchError {
+    /// The path that could not be touched.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The OS error behind the failure.
+    pub fn io_error(&self) -> &std::io::Error {
+        &self.io_error
+    }
+}
+
+impl std::fmt::Display for TouchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for TouchError {}
+
+/// Stamp one path, creating it first if it does not exist.
+pub fn touch(path: &Path, opts: &TouchOptions) -> anyhow::Result<Touched> {
+    let target = target(path, opts)?;
+    let exists = if opts.no_dereference {
+        fs::symlink_metadata(&target).is_ok()
+    } else {
+        fs::metadata(&target).is_ok()
+    };
+    if exists {
+        if opts.times.is_empty() && !opts.ctime_only {
+            return Ok(Touched::Skipped);
+        }
+        stamp(&target, opts)?;
+        return Ok(Touched::Updated);
+    }
+    if opts.no_create {
+        return Ok(Touched::Skipped);
+    }
+    if opts.parents {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("creating parent directories {:?}", parent))?;
+        }
+    }
+    File::create(&target).map_err(|err| create_error(&target, err))?;
+    // A new file starts out stamped with the current time, not the source's.
+    if !opts.times.is_empty() || opts.ctime_only {
+        stamp(&target, opts)?;
+    }
+    Ok(Touched::Created)
+}
+
+/// Touch every path with the same options, so they all get the same times (bar
+/// `Source::Now`, which each write reads afresh), and carry on past failures.
+/// Each path comes back with its own result, in order.
+pub fn touch_many(
+    paths: impl IntoIterator<Item = PathBuf>,
+    opts: &TouchOptions,
+) -> Vec<(PathBuf, anyhow::Result<()>)> {
+    paths
+        .into_iter()
+        .map(|path| {
+            let result = touch(&path, opts).map(|_| ());
+            (path, result)
+        })
+        .collect()
+}
+
+/// The access and modification times `path` has now.
+pub fn read_times(path: &Path) -> anyhow::Result<(SystemTime, SystemTime)> {
+    let meta = fs::metadata(path).with_context(|| format!("reading times {:?}", path))?;
+    times_of(path, &meta)
+}
+
+/// The access and modification times `path` has now, a symlink's own rather
+/// than its target's.
+pub fn read_link_times(path: &Path) -> anyhow::Result<(SystemTime, SystemTime)> {
+    let meta = fs::symlink_metadata(path).with_context(|| format!("reading times {:?}", path))?;
+    times_of(path, &meta)
+}
+
+fn times_of(path: &Path, meta: &fs::Metadata) -> anyhow::Result<(SystemTime, SystemTime)> {
+    let atime = meta
+        .accessed()
+        .with_context(|| format!("getting accessed time {:?}", path))?;
+    let mtime = meta
+        .modified()
+        .with_context(|| format!("getting modified time {:?}", path))?;
+    Ok((atime, mtime))
+}
+
+/// Follow a chain of symbolic links to the path it finally names, giving up
+/// after SYMLOOP_MAX links so that loops are reported instead of followed.
+pub fn resolve_symlinks(path: &Path) -> anyhow::Result<PathBuf> {
+    let mut resolved = path.to_path_buf();
+    // One pass more than SYMLOOP_MAX, to see whether the last link followed
+    // lands on something other than a link.
+    for _ in 0..=SYMLOOP_MAX {
+        match fs::symlink_metadata(&resolved) {
+            Ok(meta) if meta.file_type().is_symlink() => {
+                let target = fs::read_link(&resolved)
+                    .with_context(|| format!("reading symlink {:?}", resolved))?;
+                resolved = match resolved.parent() {
+                    Some(parent) => parent.join(target),
+                    None => target,
+                };
+            }
+            _ => return Ok(resolved),
+        }
+    }
+    anyhow::bail!("resolving {:?}: Too many levels of symbolic links", path)
+}
+
+/// Set the times `which` selects on an open file from `source`, leaving the
+/// rest as they are. The birth time, which `Source` does not carry, is set to
+/// the modification time; only Windows lets it be changed, so it is set last,
+/// after the access and modification times are already written.
+pub fn apply_times(file_obj: &File, source: &Source, which: TimeSelection) -> std::io::Result<()> {
+    let (atime, mtime) = time_specs(source, which);
+    write_times(file_obj, atime, mtime)?;
+    if which.contains(TimeSelection::BIRTH) {
+        set_birth(file_obj, source.mtime())?;
+    }
+    Ok(())
+}
+
+/// The atime and mtime to write for `which`, with `Omit` for the unselected.
+fn time_specs(source: &Source, which: TimeSelection) -> (TimeSpec, TimeSpec) {
+    let pick = |time: TimeSelection, value: SystemTime| {
+        if !which.contains(time) {
+            TimeSpec::Omit
+        } else if *source == Source::Now {
+            TimeSpec::Now
+        } else {
+            TimeSpec::from(value)
+        }
+    };
+    (
+        pick(TimeSelection::ACCESS, source.atime()),
+        pick(TimeSelection::MODIFY, source.mtime()),
+    )
+}
+
+#[cfg(windows)]
+fn set_birth(file_obj: &File, time: SystemTime) -> std::io::Result<()> {
+    use std::os::windows::fs::FileTimesExt;
+
+    file_obj.set_times(fs::FileTimes::new().set_created(time))
+}
+
+#[cfg(not(windows))]
+fn set_birth(_file_obj: &File, _time: SystemTime) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "setting the birth time is not supported on this platform",
+    ))
+}
+
+/// Rewrite a file's own mtime: the times stay put, but the kernel still counts
+/// the write as a metadata change and moves the ctime to now.
+#[cfg(unix)]
+fn bump_ctime(file_obj: &File) -> std::io::Result<()> {
+    let mtime = file_obj.metadata()?.modified()?;
+    write_times(file_obj, TimeSpec::Omit, mtime.into())
+}
+
+#[cfg(not(unix))]
+fn bump_ctime(_file_obj: &File) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "--touch-ctime: this platform has no inode change time",
+    ))
+}
+
+/// Set the access and modification times of an open file in one call, leaving
+/// a time that is `TimeSpec::Omit` untouched.
+#[cfg(unix)]
+fn write_times(file_obj: &File, atime: TimeSpec, mtime: TimeSpec) -> std::io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let times = [atime.to_timespec(), mtime.to_timespec()];
+    // SAFETY: the descriptor is owned by `file_obj` and `times` holds exactly
+    // the two entries futimens reads.
+    if unsafe { libc::futimens(file_obj.as_raw_fd(), times.as_ptr()) } == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(unix))]
+fn write_times(file_obj: &File, atime: TimeSpec, mtime: TimeSpec) -> std::io::Result<()> {
+    let mut filetimes = fs::FileTimes::new();
+    if let Some(atime) = atime.to_system_time() {
+        filetimes = filetimes.set_accessed(atime);
+    }
+    if let Some(mtime) = mtime.to_system_time() {
+        filetimes = filetimes.set_modified(mtime);
+    }
+    file_obj.set_times(filetimes)
+}
+
+/// Set the access and modification times of a symlink itself, leaving a time
+/// that is `TimeSpec::Omit` untouched.
+#[cfg(unix)]
+fn write_link_times(link: &Path, atime: TimeSpec, mtime: TimeSpec) -> std::io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(link.as_os_str().as_bytes())
+        .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidInput, err))?;
+    let times = [atime.to_timespec(), mtime.to_timespec()];
+    // SAFETY: `c_path` is NUL-terminated and outlives the call, and `times`
+    // holds exactly the two entries utimensat reads.
+    let status = unsafe {
+        libc::utimensat(
+            libc::AT_FDCWD,
+            c_path.as_ptr(),
+            times.as_ptr(),
+            libc::AT_SYMLINK_NOFOLLOW,
+        )
+    };
+    if status == 0 {
+        Ok(())
+    } else {
+        Err(std::io::Error::last_os_error())
+    }
+}
+
+#[cfg(not(unix))]
+fn write_link_times(_link: &Path, _atime: TimeSpec, _mtime: TimeSpec) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        "changing a symlink's own times is not supported on this platform",
+    ))
+}
+
+/// Stamp a symlink under `no_dereference` without opening it, which would
+/// follow it and fail on a dangling one.
+fn stamp_link(link: &Path, opts: &TouchOptions) -> anyhow::Result<()> {
+    let written = if opts.ctime_only {
+        fs::symlink_metadata(link)
+            .and_then(|meta| meta.modified())
+            .and_then(|mtime| write_link_times(link, TimeSpec::Omit, mtime.into()))
+    } else if opts.times.contains(TimeSelection::BIRTH) {
+        Err(std::io::Error::new(
+            std::io::ErrorKind::Unsupported,
+            "setting a symlink's birth time is not supported",
+        ))
+    } else {
+        let (atime, mtime) = time_specs(&opts.source, opts.times);
+        write_link_times(link, atime, mtime)
+    };
+    Ok(written.map_err(|err| touch_error(link, err))?)
+}
+
+/// Stamp an existing file with the times `opts` selects, working around its
+/// permissions and attributes under `force`.
+fn stamp(file_path: &Path, opts: &TouchOptions) -> anyhow::Result<()> {
+    let is_link = fs::symlink_metadata(file_path).is_ok_and(|meta| meta.file_type().is_symlink());
+    if opts.no_dereference && is_link {
+        return stamp_link(file_path, opts);
+    }
+    let mut _restore_mode = None;
+    let file_obj = match fs::File::open(file_path) {
+        Ok(file_obj) => file_obj,
+        Err(err) if opts.force && err.kind() == std::io::ErrorKind::PermissionDenied => {
+            _restore_mode = Some(ModeGuard::open_up(file_path)?);
+            fs::File::open(file_path).map_err(|err| open_error(file_path, err))?
+        }
+        Err(err) => return Err(open_error(file_path, err).into()),
+    };
+
+    let write = || {
+        if opts.ctime_only {
+            bump_ctime(&file_obj)
+        } else {
+            apply_times(&file_obj, &opts.source, opts.times)
+        }
+    };
+
+    let written = write();
+    #[cfg(target_os = "linux")]
+    let written = match written {
+        Err(err) if opts.force && err.raw_os_error() == Some(libc::EPERM) => {
+            match AttrGuard::clear(&file_obj) {
+                Ok(Some(_restore_attrs)) => write(),
+                _ => Err(err),
+            }
+        }
+        written => written,
+    };
+
+    Ok(written.map_err(|err| match err.raw_os_error() {
+        #[cfg(target_os = "linux")]
+        Some(libc::EPERM) => match AttrGuard::locked_by(&file_obj) {
+            Some(attribute) => TouchError {
+                path: file_path.to_path_buf(),
+                message: format!(
+                    "cannot touch '{}': Operation not permitted (file is {})",
+                    file_path.display(),
+                    attribute
+                ),
+                io_error: err,
+            },
+            None => touch_error(file_path, err),
+        },
+        _ => touch_error(file_path, err),
+    })?)
+}
+
+/// Clears a file's immutable and append-only attributes for --force, putting
+/// them back when dropped. Changing them needs CAP_LINUX_IMMUTABLE.
+#[cfg(target_os = "linux")]
+struct AttrGuard<'a> {
+    file_obj: &'a File,
+    original: libc::c_uint,
+}
+
+#[cfg(target_os = "linux")]
+impl<'a> AttrGuard<'a> {
+    const FS_IMMUTABLE_FL: libc::c_uint = 0x10;
+    const FS_APPEND_FL: libc::c_uint = 0x20;
+
+    /// Clear the attributes, or return `None` when neither is set.
+    fn clear(file_obj: &'a File) -> std::io::Result<Option<Self>> {
+        let original = Self::get(file_obj)?;
+        let cleared = original & !(Self::FS_IMMUTABLE_FL | Self::FS_APPEND_FL);
+        if cleared == original {
+            return Ok(None);
+        }
+        Self::set(file_obj, cleared)?;
+        Ok(Some(AttrGuard { file_obj, original }))
+    }
+
+    /// The attribute that forbids changing the file's times, if either is set.
+    fn locked_by(file_obj: &File) -> Option<&'static str> {
+        let flags = Self::get(file_obj).ok()?;
+        if flags & Self::FS_IMMUTABLE_FL != 0 {
+            Some("immutable")
+        } else if flags & Self::FS_APPEND_FL != 0 {
+            Some("append-only")
+        } else {
+            None
+        }
+    }
+
+    fn get(file_obj: &File) -> std::io::Result<libc::c_uint> {
+        use std::os::unix::io::AsRawFd;
+
+        let mut flags: libc::c_uint = 0;
+        // SAFETY: FS_IOC_GETFLAGS writes one int through the pointer.
+        match unsafe { libc::ioctl(file_obj.as_raw_fd(), libc::FS_IOC_GETFLAGS, &mut flags) } {
+            0 => Ok(flags),
+            _ => Err(std::io::Error::last_os_error()),
+        }
+    }
+
+    fn set(file_obj: &File, flags: libc::c_uint) -> std::io::Result<()> {
+        use std::os::unix::io::AsRawFd;
+
+        // SAFETY: FS_IOC_SETFLAGS reads one int through the pointer.
+        match unsafe { libc::ioctl(file_obj.as_raw_fd(), libc::FS_IOC_SETFLAGS, &flags) } {
+            0 => Ok(()),
+            _ => Err(std::io::Error::last_os_error()),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for AttrGuard<'_> {
+    fn drop(&mut self) {
+        if let Err(err) = Self::set(self.file_obj, self.original) {
+            warn(format_args!("restoring file attributes: {}", err));
+        }
+    }
+}
+
+/// Puts back a file's original permissions when dropped, so that --force leaves
+/// the mode as it found it even when setting the times fails.
+struct ModeGuard {
+    path: PathBuf,
+    original: fs::Permissions,
+}
+
+impl ModeGuard {
+    fn open_up(path: &Path) -> anyhow::Result<Self> {
+        let original = fs::metadata(path)
+            .with_context(|| format!("reading permissions {:?}", path))?
+            .permissions();
+        let mut opened = original.clone();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            opened.set_mode(opened.mode() | 0o600);
+        }
+        #[cfg(not(unix))]
+        opened.set_readonly(false);
+        fs::set_permissions(path, opened)
+            .with_context(|| format!("changing permissions {:?}", path))?;
+        Ok(ModeGuard {
+            path: path.to_path_buf(),
+            original,
+        })
+    }
+}
+
+impl Drop for ModeGuard {
+    fn drop(&mut self) {
+        if let Err(err) = fs::set_permissions(&self.path, self.original.clone()) {
+            warn(format_args!(
+                "restoring permissions {:?}: {}",
+                self.path, err
+            ));
+        }
+    }
+}
+
+/// Describe a failure to create or stamp `path`, spelling out causes that the
+/// bare OS error leaves unclear.
+fn touch_error(path: &Path, err: std::io::Error) -> TouchError {
+    let path_shown = path.display();
+    let message = match err.kind() {
+        std::io::ErrorKind::ReadOnlyFilesystem => {
+            format!("cannot touch '{}': Read-only file system", path_shown)
+        }
+        _ => format!("cannot touch '{}': {}", path_shown, err),
+    };
+    TouchError {
+        path: path.to_path_buf(),
+        message,
+        io_error: err,
+    }
+}
+
+/// Describe a failure to create `path`, naming the directory when it is what
+/// denied permission.
+fn create_error(path: &Path, err: std::io::Error) -> TouchError {
+    if err.kind() != std::io::ErrorKind::PermissionDenied {
+        return touch_error(path, err);
+    }
+    let dir = match path.parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
+    };
+    TouchError {
+        path: path.to_path_buf(),
+        message: format!(
+            "cannot touch '{}': Permission denied (cannot create files in directory '{}')",
+            path.display(),
+            dir.display()
+        ),
+        io_error: err,
+    }
+}
+
+/// Describe a failure to open `path` before stamping it.
+fn open_error(path: &Path, err: std::io::Error) -> TouchError {
+    TouchError {
+        path: path.to_path_buf(),
+        message: format!("opening file {:?}: {}", path, err),
+        io_error: err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A fresh directory for one test under the system's temporary directory.
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("tick-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    /// A chain of `links` symlinks in `dir` ending at a file, and its first link.
+    #[cfg(unix)]
+    fn symlink_chain(dir: &Path, links: usize) -> PathBuf {
+        fs::write(dir.join("file"), "").unwrap();
+        let mut target = PathBuf::from("file");
+        for i in 0..links {
+            let link = PathBuf::from(format!("link{}", i));
+            std::os::unix::fs::symlink(&target, dir.join(&link)).unwrap();
+            target = link;
+        }
+        dir.join(target)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_chain_of_symloop_max_links_resolves() {
+        let dir = scratch_dir("chain-max");
+        let first = symlink_chain(&dir, SYMLOOP_MAX);
+        assert_eq!(resolve_symlinks(&first).unwrap(), dir.join("file"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_chain_longer_than_symloop_max_is_a_loop() {
+        let dir = scratch_dir("chain-over");
+        let first = symlink_chain(&dir, SYMLOOP_MAX + 1);
+        let err = resolve_symlinks(&first).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Too many levels of symbolic links"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_self_referential_symlink_is_a_loop() {
+        let dir = scratch_dir("self-link");
+        std::os::unix::fs::symlink("self", dir.join("self")).unwrap();
+        let err = resolve_symlinks(&dir.join("self")).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Too many levels of symbolic links"));
+    }
+
+    #[test]
+    fn now_is_left_to_the_kernel() {
+        let both = TimeSelection::ACCESS | TimeSelection::MODIFY;
+        assert_eq!(
+            time_specs(&Source::Now, both),
+            (TimeSpec::Now, TimeSpec::Now)
+        );
+        assert_eq!(
+            time_specs(&Source::Now, TimeSelection::MODIFY),
+            (TimeSpec::Omit, TimeSpec::Now)
+        );
+    }
+
+    #[test]
+    fn a_read_only_file_system_is_named_plainly() {
+        let err = std::io::Error::from(std::io::ErrorKind::ReadOnlyFilesystem);
+        assert_eq!(
+            touch_error(Path::new("mnt/file"), err).message,
+            "cannot touch 'mnt/file': Read-only file system"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn erofs_is_a_read_only_file_system() {
+        let err = std::io::Error::from_raw_os_error(libc::EROFS);
+        let touch_error = touch_error(Path::new("file"), err);
+        assert_eq!(
+            touch_error.message,
+            "cannot touch 'file': Read-only file system"
+        );
+        assert_eq!(touch_error.io_error.raw_os_error(), Some(libc::EROFS));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_mode_guard_puts_the_mode_back_when_dropped() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = scratch_dir("mode-guard");
+        let file = dir.join("file");
+        fs::write(&file, "").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o400)).unwrap();
+        let mode = || fs::metadata(&file).unwrap().permissions().mode() & 0o777;
+
+        let guard = ModeGuard::open_up(&file).unwrap();
+        assert_eq!(mode(), 0o600);
+        drop(guard);
+        assert_eq!(mode(), 0o400);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn force_restores_the_mode_when_stamping_fails() {
+        use std::os::unix::fs::PermissionsExt;
+
+        if unsafe { libc::geteuid() } == 0 {
+            eprintln!("skipped: root opens the file without --force opening it up");
+            return;
+        }
+        let dir = scratch_dir("force-failure");
+        let file = dir.join("file");
+        fs::write(&file, "").unwrap();
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o000)).unwrap();
+
+        // Only Windows sets a birth time, so this fails after the mode is opened up.
+        let opts = TouchOptions {
+            times: TimeSelection::BIRTH,
+            force: true,
+            ..Default::default()
+        };
+        assert!(stamp(&file, &opts).is_err());
+        let mode = fs::metadata(&file).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o000);
+    }
+
+    #[test]
+    fn touch_many_carries_on_past_a_failure() {
+        let dir = scratch_dir("touch-many");
+        let paths = [dir.join("one"), dir.join("missing/two"), dir.join("three")];
+        let results = touch_many(paths.clone(), &TouchOptions::default());
+
+        let returned: Vec<&PathBuf> = results.iter().map(|(path, _)| path).collect();
+        assert_eq!(returned, paths.iter().collect::<Vec<_>>());
+        assert!(results[0].1.is_ok());
+        assert!(results[1].1.is_err());
+        assert!(results[2].1.is_ok());
+        assert!(paths[0].is_file() && paths[2].is_file());
+    }
+
+    fn epoch(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs)
+    }
+
+    /// Apply `which` from `new` to a file whose times are both `old`, and return
+    /// the result with the times the file is left with.
+    fn apply_to_fresh_file(
+        dir: &Path,
+        which: TimeSelection,
+    ) -> (std::io::Result<()>, (SystemTime, SystemTime)) {
+        let path = dir.join(format!("file{}", which.0));
+        File::create(&path).unwrap();
+        let file_obj = File::open(&path).unwrap();
+        let both = TimeSelection::ACCESS | TimeSelection::MODIFY;
+        apply_times(&file_obj, &Source::from(epoch(1_000_000_000)), both).unwrap();
+
+        let new = Source::from((epoch(1_100_000_000), epoch(1_200_000_000)));
+        let result = apply_times(&file_obj, &new, which);
+        (result, read_times(&path).unwrap())
+    }
+
+    #[test]
+    fn apply_times_writes_just_the_times_selected() {
+        let dir = scratch_dir("apply-times");
+        let (old, atime, mtime) = (
+            epoch(1_000_000_000),
+            epoch(1_100_000_000),
+            epoch(1_200_000_000),
+        );
+        let access = TimeSelection::ACCESS;
+        let modify = TimeSelection::MODIFY;
+        for (which, expected) in [
+            (TimeSelection::empty(), (old, old)),
+            (access, (atime, old)),
+            (modify, (old, mtime)),
+            (access | modify, (atime, mtime)),
+        ] {
+            let (result, times) = apply_to_fresh_file(&dir, which);
+            assert!(result.is_ok(), "{:?}", which);
+            assert_eq!(times, expected, "{:?}", which);
+        }
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn an_unsupported_birth_time_fails_after_the_other_times_are_written() {
+        let dir = scratch_dir("apply-birth");
+        let (old, atime, mtime) = (
+            epoch(1_000_000_000),
+            epoch(1_100_000_000),
+            epoch(1_200_000_000),
+        );
+        let birth = TimeSelection::BIRTH;
+        for (which, expected) in [
+            (birth, (old, old)),
+            (birth | TimeSelection::ACCESS, (atime, old)),
+            (
+                birth | TimeSelection::ACCESS | TimeSelection::MODIFY,
+                (atime, mtime),
+            ),
+        ] {
+            let (result, times) = apply_to_fresh_file(&dir, which);
+            let err = result.unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::Unsupported, "{:?}", which);
+            assert_eq!(times, expected, "{:?}", which);
+        }
+    }
+
+    #[test]
+    fn one_time_sets_both() {
+        let time = epoch(42);
+        let source = Source::from(time);
+        assert_eq!(source, Source::Single(time));
+        assert_eq!((source.atime(), source.mtime()), (time, time));
+    }
+
+    #[test]
+    fn a_pair_is_atime_then_mtime() {
+        let (atime, mtime) = (epoch(1), epoch(2));
+        let source = Source::from((atime, mtime));
+        assert_eq!(source, Source::Multi(atime, mtime));
+        assert_eq!((source.atime(), source.mtime()), (atime, mtime));
+    }
+
+    #[test]
+    fn a_refused_create_names_the_directory() {
+        let denied = || std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert_eq!(
+            create_error(Path::new("locked/file"), denied()).message,
+            "cannot touch 'locked/file': Permission denied (cannot create files in directory 'locked')"
+        );
+        assert_eq!(
+            create_error(Path::new("file"), denied()).message,
+            "cannot touch 'file': Permission denied (cannot create files in directory '.')"
+        );
+        let missing = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert_eq!(
+            create_error(Path::new("missing/file"), missing).message,
+            touch_error(
+                Path::new("missing/file"),
+                std::io::Error::from(std::io::ErrorKind::NotFound)
+            )
+            .message
+        );
+    }
+}
@@ -0,0 +1,193 @@
+mod common;
+
+use common::{command, epoch, run, scratch_dir, tick, times};
+
+#[test]
+fn words_after_double_dash_are_files_even_when_they_name_options() {
+    let dir = scratch_dir("cli-double-dash");
+    tick(&dir, &["--", "-m", "--date", "-c"]);
+    for name in ["-m", "--date", "-c"] {
+        assert!(dir.join(name).is_file(), "{:?} was not created", name);
+    }
+}
+
+#[test]
+fn a_dash_leading_file_is_created_and_stamped_after_double_dash() {
+    let dir = scratch_dir("cli-dash-file");
+    tick(&dir, &["--", "-weird"]);
+    tick(&dir, &["-d", "@1000000000", "--", "-weird"]);
+    assert_eq!(times(&dir.join("-weird")).1, epoch(1_000_000_000, 0));
+}
+
+#[test]
+fn a_dash_leading_file_without_double_dash_is_pointed_at_it() {
+    let dir = scratch_dir("cli-dash-hint");
+    let output = run(&dir, &["-weird"]);
+    assert_eq!(output.status.code(), Some(2));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("to touch a file named '-weird', use 'tick -- -weird'"),
+        "{}",
+        stderr
+    );
+    assert!(!dir.join("-weird").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn a_non_utf8_word_is_a_usage_error_not_a_panic() {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    let dir = scratch_dir("cli-non-utf8");
+    let output = command(&dir)
+        .args([OsStr::new("-q"), OsStr::from_bytes(b"\xff")])
+        .output()
+        .expect("running tick");
+    assert_eq!(output.status.code(), Some(2));
+}
+
+/// Assert that tick exited with `code`, printing the hint line exactly when the
+/// code is 2.
+fn assert_exit(output: &std::process::Output, code: i32) {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(code), "stderr: {}", stderr);
+    assert_eq!(
+        stderr.contains("Try 'tick --help' for more information"),
+        code == 2,
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn conflicting_time_sources_exit_2() {
+    let dir = scratch_dir("cli-conflict");
+    tick(&dir, &["ref"]);
+    let output = run(&dir, &["-d", "2024-01-15", "-r", "ref", "file"]);
+    assert_exit(&output, 2);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("at the same time"));
+    assert!(!dir.join("file").exists());
+}
+
+#[test]
+fn a_date_tick_cannot_read_exits_2_wherever_it_comes_from() {
+    let dir = scratch_dir("cli-bad-date");
+    std::fs::write(dir.join("empty"), "\n  \n").unwrap();
+    std::fs::write(dir.join("garbled"), "not a date\n").unwrap();
+    for args in [
+        &["-d", "not a date", "file"][..],
+        &["-t", "2024011", "file"],
+        &["--date-file", "empty", "file"],
+        &["--date-file", "garbled", "file"],
+    ] {
+        assert_exit(&run(&dir, args), 2);
+    }
+    assert!(!dir.join("file").exists());
+}
+
+#[test]
+fn a_date_file_tick_cannot_open_exits_1() {
+    let dir = scratch_dir("cli-missing-date-file");
+    assert_exit(&run(&dir, &["--date-file", "missing", "file"]), 1);
+}
+
+#[test]
+fn a_touch_refused_permission_exits_1() {
+    let dir = scratch_dir("cli-denied");
+    let Some(denied) = common::Denied::arrange(&dir) else {
+        eprintln!("skipped: cannot arrange a permission failure here");
+        return;
+    };
+    let output = command(&dir).arg(&denied.path).output().unwrap();
+    assert_exit(&output, 1);
+    assert!(String::from_utf8_lossy(&output.stderr).contains("cannot touch"));
+}
+
+#[test]
+fn braces_expand_before_double_dash_but_not_after_it() {
+    let dir = scratch_dir("cli-braces");
+    tick(&dir, &["x{1,2}", "--", "lit{a,b}"]);
+    for name in ["x1", "x2", "lit{a,b}"] {
+        assert!(dir.join(name).is_file(), "{:?} was not created", name);
+    }
+    assert!(!dir.join("lita").exists());
+}
+
+#[test]
+fn usage_errors_end_with_the_help_hint() {
+    let dir = scratch_dir("cli-hint");
+    tick(&dir, &["ref"]);
+    for (args, message) in [
+        (&[][..], "tick: missing operand"),
+        (
+            &["-d", "2024-01-15", "-r", "ref", "file"],
+            "tick: Cannot use -t, -d, or -r at the same time",
+        ),
+        (&["-d", "not a date", "file"], "tick: parsing date string"),
+    ] {
+        let output = run(&dir, args);
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert!(stderr.starts_with(message), "stderr: {}", stderr);
+        assert!(
+            stderr.ends_with("\nTry 'tick --help' for more information\n"),
+            "stderr: {}",
+            stderr
+        );
+    }
+}
+
+#[test]
+fn a_file_that_fails_does_not_stop_the_rest() {
+    let dir = scratch_dir("cli-carry-on");
+    for args in [
+        &["one", "missing/two", "three"][..],
+        &["-v", "one", "missing/two", "three"],
+    ] {
+        let output = run(&dir, args);
+        assert_eq!(output.status.code(), Some(1));
+        let stderr = String::from_utf8(output.stderr).unwrap();
+        assert_eq!(stderr.lines().count(), 1, "stderr: {}", stderr);
+        assert!(stderr.starts_with("tick: cannot touch 'missing/two'"));
+        assert!(dir.join("one").is_file() && dir.join("three").is_file());
+    }
+}
+
+#[test]
+fn json_errors_describe_a_permission_failure() {
+    let dir = scratch_dir("cli-json-denied");
+    let Some(denied) = common::Denied::arrange(&dir) else {
+        eprintln!("skipped: cannot arrange a permission failure here");
+        return;
+    };
+    let path = denied.path.strip_prefix(&dir).unwrap().to_str().unwrap();
+    let output = run(&dir, &["--json-errors", path]);
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    let prefix = format!(
+        "{{\"path\":\"{}\",\"kind\":\"PermissionDenied\",\"message\":\"cannot touch '{}': ",
+        path, path
+    );
+    assert!(stderr.starts_with(&prefix), "stderr: {}", stderr);
+    // Root is refused by an immutable file (EPERM), anyone else by the
+    // directory's mode (EACCES).
+    assert!(
+        stderr.ends_with("\"errno\":1}\n") || stderr.ends_with("\"errno\":13}\n"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn exit_missing_stops_at_a_missing_directory_with_status_3() {
+    let dir = scratch_dir("cli-exit-missing");
+    let output = run(
+        &dir,
+        &["--exit-missing", "first", "missing/second", "third"],
+    );
+    assert_eq!(output.status.code(), Some(3));
+    assert!(dir.join("first").is_file());
+    assert!(!dir.join("third").exists());
+
+    // Without the flag a missing directory is an ordinary failure.
+    assert_eq!(run(&dir, &["missing/second"]).status.code(), Some(1));
+}
@@ -0,0 +1,112 @@
+//! Helpers shared by the integration tests, which run the tick binary in a
+//! scratch directory of their own.
+
+// Each test file uses only some of these.
+#![allow(dead_code)]
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Output},
+    time::{Duration, SystemTime},
+};
+
+/// A tick command run from `dir`.
+pub fn command(dir: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_tick"));
+    command.current_dir(dir);
+    command
+}
+
+/// Run tick from `dir`, whatever its exit status.
+pub fn run(dir: &Path, args: &[&str]) -> Output {
+    command(dir).args(args).output().expect("running tick")
+}
+
+/// Run tick from `dir`, asserting that it succeeds, and return its stdout.
+pub fn tick(dir: &Path, args: &[&str]) -> String {
+    let output = run(dir, args);
+    assert!(
+        output.status.success(),
+        "tick {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("tick printed UTF-8")
+}
+
+/// A fresh directory of the calling test's own under Cargo's scratch space.
+pub fn scratch_dir(name: &str) -> PathBuf {
+    let dir =
+        Path::new(env!("CARGO_TARGET_TMPDIR")).join(format!("{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).expect("creating scratch directory");
+    dir
+}
+
+/// The access and modification times `path` has.
+pub fn times(path: &Path) -> (SystemTime, SystemTime) {
+    let meta = fs::metadata(path).expect("reading times");
+    (meta.accessed().unwrap(), meta.modified().unwrap())
+}
+
+/// `secs` and `nanos` after the epoch.
+pub fn epoch(secs: u64, nanos: u32) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::new(secs, nanos)
+}
+
+/// A path in `dir` that tick is refused permission to touch, undone on drop so
+/// the scratch directory can be cleaned up.
+pub struct Denied {
+    pub path: PathBuf,
+    immutable: bool,
+}
+
+impl Denied {
+    /// A new file in a read-only directory, or for root, who may write there
+    /// anyway, an immutable file. None when neither can be arranged, as for root
+    /// without chattr or the capability to use it.
+    #[cfg(unix)]
+    pub fn arrange(dir: &Path) -> Option<Denied> {
+        use std::os::unix::fs::PermissionsExt;
+
+        if unsafe { libc::geteuid() } != 0 {
+            let locked = dir.join("locked");
+            fs::create_dir(&locked).expect("creating locked directory");
+            fs::set_permissions(&locked, fs::Permissions::from_mode(0o555)).unwrap();
+            return Some(Denied {
+                path: locked.join("new"),
+                immutable: false,
+            });
+        }
+        let path = dir.join("immutable");
+        fs::write(&path, "").expect("creating immutable file");
+        let locked = Command::new("chattr")
+            .arg("+i")
+            .arg(&path)
+            .output()
+            .is_ok_and(|output| output.status.success());
+        locked.then_some(Denied {
+            path,
+            immutable: true,
+        })
+    }
+
+    #[cfg(not(unix))]
+    pub fn arrange(_dir: &Path) -> Option<Denied> {
+        None
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Denied {
+    fn drop(&mut self) {
+        use std::os::unix::fs::PermissionsExt;
+
+        if self.immutable {
+            let _ = Command::new("chattr").arg("-i").arg(&self.path).status();
+        } else if let Some(locked) = self.path.parent() {
+            let _ = fs::set_permissions(locked, fs::Permissions::from_mode(0o755));
+        }
+    }
+}
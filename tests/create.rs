@@ -0,0 +1,35 @@
+mod common;
+
+use common::{epoch, run, scratch_dir, tick, times};
+
+#[test]
+fn a_new_file_takes_a_date_given_with_d() {
+    let dir = scratch_dir("create-date");
+    tick(&dir, &["-d", "@1000000000.5", "new"]);
+    let time = epoch(1_000_000_000, 500_000_000);
+    assert_eq!(times(&dir.join("new")), (time, time));
+}
+
+#[test]
+fn a_new_file_takes_the_reference_times() {
+    let dir = scratch_dir("create-reference");
+    tick(&dir, &["-d", "@1000000000", "ref"]);
+    tick(&dir, &["-r", "ref", "new"]);
+    assert_eq!(times(&dir.join("new")), times(&dir.join("ref")));
+}
+
+#[test]
+fn no_create_passes_over_a_missing_file_silently() {
+    let dir = scratch_dir("create-no-create");
+    for args in [
+        &["-c", "missing.txt"][..],
+        &["-c", "-v", "missing.txt"],
+        &["-c", "missing/missing.txt"],
+    ] {
+        let output = run(&dir, args);
+        assert!(output.status.success());
+        assert!(output.stdout.is_empty(), "{:?} printed", args);
+        assert!(output.stderr.is_empty(), "{:?} complained", args);
+    }
+    assert!(!dir.join("missing.txt").exists());
+}
@@ -0,0 +1,61 @@
+// These tests pin TZ to an IANA zone name, which Windows does not read.
+#![cfg(unix)]
+
+mod common;
+
+use std::path::Path;
+
+use common::{command, epoch, scratch_dir, times};
+
+/// Run tick from `dir` with TZ set to `zone`, asserting that it succeeds.
+fn tick_in_zone(dir: &Path, zone: &str, args: &[&str]) {
+    let output = command(dir).env("TZ", zone).args(args).output().unwrap();
+    assert!(
+        output.status.success(),
+        "tick {:?} failed: {}",
+        args,
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn a_time_in_the_spring_gap_moves_forward_an_hour() {
+    let dir = scratch_dir("dates-dst-gap");
+    tick_in_zone(&dir, "America/New_York", &["-d", "2024-03-10 02:30", "d"]);
+    tick_in_zone(&dir, "America/New_York", &["-t", "202403100230", "t"]);
+    // 03:30 EDT.
+    let instant = epoch(1_710_055_800, 0);
+    assert_eq!(times(&dir.join("d")), (instant, instant));
+    assert_eq!(times(&dir.join("t")), (instant, instant));
+}
+
+#[test]
+fn a_time_in_the_autumn_overlap_takes_the_earlier_instant() {
+    let dir = scratch_dir("dates-dst-overlap");
+    tick_in_zone(&dir, "America/New_York", &["-d", "2024-11-03 01:30", "d"]);
+    tick_in_zone(&dir, "America/New_York", &["-t", "202411030130", "t"]);
+    // 01:30 EDT, not the 01:30 EST an hour later.
+    let instant = epoch(1_730_611_800, 0);
+    assert_eq!(times(&dir.join("d")), (instant, instant));
+    assert_eq!(times(&dir.join("t")), (instant, instant));
+}
+
+#[test]
+fn date_output_in_the_local_zone_reads_either_abbreviation() {
+    let dir = scratch_dir("dates-date-output");
+    let zone = "America/New_York";
+    tick_in_zone(
+        &dir,
+        zone,
+        &["-d", "Mon Jan 15 10:00:00 EST 2024", "winter"],
+    );
+    tick_in_zone(
+        &dir,
+        zone,
+        &["-d", "Mon Jul 15 10:00:00 EDT 2024", "summer"],
+    );
+    let winter = epoch(1_705_330_800, 0);
+    let summer = epoch(1_721_052_000, 0);
+    assert_eq!(times(&dir.join("winter")), (winter, winter));
+    assert_eq!(times(&dir.join("summer")), (summer, summer));
+}
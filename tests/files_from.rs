@@ -0,0 +1,83 @@
+mod common;
+
+use std::{
+    io::Write,
+    path::Path,
+    process::{Output, Stdio},
+};
+
+use common::{command, epoch, scratch_dir, times};
+
+/// Run tick from `dir` with `input` piped to its stdin.
+fn run_with_stdin(dir: &Path, args: &[&str], input: &[u8]) -> Output {
+    let mut child = command(dir)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(input).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+#[test]
+fn a_dash_reads_the_list_from_stdin() {
+    let dir = scratch_dir("files-from-stdin");
+    let output = run_with_stdin(&dir, &["--files-from", "-"], b"one\ntwo words\n\n");
+    assert!(output.status.success());
+    assert!(dir.join("one").is_file());
+    assert!(dir.join("two words").is_file());
+}
+
+#[test]
+#[ignore = "touches 100,000 files; run with --ignored"]
+fn a_long_file_list_is_touched_in_full() {
+    let dir = scratch_dir("files-from-long");
+    let names: Vec<String> = (0..100_000).map(|i| format!("f{}", i)).collect();
+    std::fs::write(dir.join("list"), names.join("\n")).unwrap();
+
+    let output = command(&dir)
+        .args(["-d", "@1000000000", "--files-from", "list"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stamped = epoch(1_000_000_000, 0);
+    for name in [&names[0], &names[50_000], &names[99_999]] {
+        assert_eq!(times(&dir.join(name)), (stamped, stamped), "{}", name);
+    }
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), names.len() + 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+// Windows does not allow a newline in a file name.
+#[cfg(unix)]
+#[test]
+fn null_data_reads_and_writes_nul_separated_names() {
+    let dir = scratch_dir("files-from-nul");
+    let output = run_with_stdin(
+        &dir,
+        &["-z", "-v", "--files-from", "-"],
+        b"a\0line\nbreak\0",
+    );
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"created 'a'\0created 'line\nbreak'\0");
+    assert!(dir.join("line\nbreak").is_file());
+}
+
+#[test]
+fn a_crlf_list_on_stdin_names_files_without_the_carriage_returns() {
+    let dir = scratch_dir("files-from-crlf");
+    let output = run_with_stdin(
+        &dir,
+        &["--strip-trailing-whitespace", "--files-from", "-"],
+        b"one\r\ntwo  \r\n",
+    );
+    assert!(output.status.success());
+    let mut names: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    names.sort();
+    assert_eq!(names, ["one", "two"]);
+}
@@ -0,0 +1,146 @@
+mod common;
+
+use std::process::Output;
+
+use common::{command, scratch_dir, tick};
+
+/// Run tick with an unparseable date, which fails before touching anything,
+/// clearing any colour settings the environment has and setting those in `env`.
+fn fail_with_env(env: &[(&str, &str)], args: &[&str]) -> Output {
+    let mut command = command(&std::env::temp_dir());
+    for var in ["NO_COLOR", "CLICOLOR", "CLICOLOR_FORCE", "TERM"] {
+        command.env_remove(var);
+    }
+    command
+        .envs(env.iter().copied())
+        .args(args)
+        .args(["-d", "not a date", "file"])
+        .output()
+        .unwrap()
+}
+
+fn is_coloured(output: &Output) -> bool {
+    assert!(!output.status.success());
+    output.stderr.contains(&b'\x1b')
+}
+
+#[test]
+fn errors_are_coloured_only_when_asked_for() {
+    assert!(!is_coloured(&fail_with_env(&[], &[])));
+    assert!(is_coloured(&fail_with_env(&[("CLICOLOR_FORCE", "1")], &[])));
+    assert!(is_coloured(&fail_with_env(&[], &["--color=always"])));
+}
+
+#[test]
+fn no_color_turns_colour_off_unless_it_is_forced_with_a_flag() {
+    let no_color = ("NO_COLOR", "1");
+    assert!(!is_coloured(&fail_with_env(
+        &[("CLICOLOR_FORCE", "1"), no_color],
+        &[]
+    )));
+    assert!(is_coloured(&fail_with_env(
+        &[no_color],
+        &["--color=always"]
+    )));
+    assert!(!is_coloured(&fail_with_env(
+        &[("CLICOLOR_FORCE", "1")],
+        &["--color=never"]
+    )));
+}
+
+// Windows does not read TZ as an IANA zone name.
+#[cfg(unix)]
+#[test]
+fn print_shows_local_times_unless_utc_is_given() {
+    let dir = scratch_dir("output-print-zone");
+    tick(&dir, &["-d", "@1000000000.5", "ref", "file"]);
+    let print = |args: &[&str]| {
+        let output = command(&dir)
+            .env("TZ", "Asia/Kolkata")
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    let local = "2001-09-09T07:16:40.500000000+05:30";
+    let utc = "2001-09-09T01:46:40.500000000+00:00";
+    assert_eq!(
+        print(&["--print", "--time=none", "file"]),
+        format!("{} {} file\n", local, local)
+    );
+    assert_eq!(
+        print(&["--print", "--utc", "-r", "ref", "file"]),
+        format!("{} {} file\n", utc, utc)
+    );
+}
+
+#[test]
+fn double_verbose_shows_the_old_and_new_times() {
+    let dir = scratch_dir("output-verbose-times");
+    tick(&dir, &["-d", "@1000000000", "file"]);
+
+    let old = "2001-09-09T01:46:40.000000000+00:00";
+    let new = "2017-07-14T02:40:00.000000000+00:00";
+    assert_eq!(
+        tick(&dir, &["-vv", "--utc", "-d", "@1500000000", "file"]),
+        format!(
+            "updated 'file': atime {} -> {}, mtime {} -> {}\n",
+            old, new, old, new
+        )
+    );
+    assert_eq!(
+        tick(&dir, &["-vv", "--utc", "-d", "@1500000000", "new"]),
+        format!("created 'new': atime {}, mtime {}\n", new, new)
+    );
+}
+
+#[test]
+fn verbose_says_whether_each_file_was_created_or_updated() {
+    let dir = scratch_dir("output-verbose-verbs");
+    tick(&dir, &["old"]);
+    assert_eq!(
+        tick(&dir, &["-v", "old", "new"]),
+        "updated 'old'\ncreated 'new'\n"
+    );
+    assert_eq!(tick(&dir, &["-v", "new"]), "updated 'new'\n");
+}
+
+#[test]
+fn timestamp_file_logs_the_times_applied_to_each_file() {
+    let dir = scratch_dir("output-timestamp-file");
+    tick(
+        &dir,
+        &[
+            "--utc",
+            "--timestamp-file",
+            "log",
+            "-d",
+            "@1000000000",
+            "a",
+            "b",
+        ],
+    );
+    tick(
+        &dir,
+        &[
+            "--utc",
+            "--timestamp-file",
+            "log",
+            "-d",
+            "@1500000000.25",
+            "a",
+        ],
+    );
+
+    let first = "2001-09-09T01:46:40.000000000+00:00";
+    let second = "2017-07-14T02:40:00.250000000+00:00";
+    assert_eq!(
+        std::fs::read_to_string(dir.join("log")).unwrap(),
+        format!(
+            "{} {} a\n{} {} b\n{} {} a\n",
+            first, first, first, first, second, second
+        )
+    );
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use common::{epoch, scratch_dir, tick, times};
+
+#[cfg(unix)]
+fn is_root() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(unix)]
+#[test]
+fn force_stamps_an_unreadable_file_and_restores_its_mode() {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    if is_root() {
+        eprintln!("skipped: root needs no --force to open the file");
+        return;
+    }
+    let dir = scratch_dir("permissions-force");
+    let file = dir.join("locked");
+    fs::write(&file, "").unwrap();
+    fs::set_permissions(&file, fs::Permissions::from_mode(0o000)).unwrap();
+
+    tick(&dir, &["--force", "-d", "@1000000000", "locked"]);
+    let stamped = epoch(1_000_000_000, 0);
+    assert_eq!(times(&file), (stamped, stamped));
+    assert_eq!(fs::metadata(&file).unwrap().permissions().mode() & 0o777, 0);
+}
+
+/// A file attribute set with chattr, cleared again on drop so the scratch
+/// directory can be cleaned up.
+#[cfg(target_os = "linux")]
+struct Attribute<'a> {
+    path: &'a std::path::Path,
+    flag: &'static str,
+}
+
+#[cfg(target_os = "linux")]
+impl<'a> Attribute<'a> {
+    /// Set `flag` ('i' or 'a') on `path`, or None without chattr or the
+    /// capability to use it.
+    fn set(path: &'a std::path::Path, flag: &'static str) -> Option<Self> {
+        let status = std::process::Command::new("chattr")
+            .arg(format!("+{}", flag))
+            .arg(path)
+            .output();
+        status
+            .is_ok_and(|output| output.status.success())
+            .then_some(Attribute { path, flag })
+    }
+
+    fn is_set(&self) -> bool {
+        let output = std::process::Command::new("lsattr")
+            .arg(self.path)
+            .output()
+            .unwrap();
+        let listed = String::from_utf8_lossy(&output.stdout);
+        listed
+            .split_whitespace()
+            .next()
+            .unwrap()
+            .contains(self.flag)
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Attribute<'_> {
+    fn drop(&mut self) {
+        let _ = std::process::Command::new("chattr")
+            .arg(format!("-{}", self.flag))
+            .arg(self.path)
+            .status();
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn a_locked_file_is_named_as_such_and_force_unlocks_it_for_the_stamp() {
+    let dir = scratch_dir("permissions-attributes");
+    for (flag, name, lock) in [
+        ("i", "immutable", "immutable"),
+        ("a", "append", "append-only"),
+    ] {
+        let file = dir.join(name);
+        std::fs::write(&file, "").unwrap();
+        let Some(attribute) = Attribute::set(&file, flag) else {
+            eprintln!("skipped: chattr +{} needs CAP_LINUX_IMMUTABLE", flag);
+            return;
+        };
+
+        let output = common::run(&dir, &["-d", "@1000000000", name]);
+        assert_eq!(output.status.code(), Some(1));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            format!(
+                "tick: cannot touch '{}': Operation not permitted (file is {})\n",
+                name, lock
+            )
+        );
+
+        tick(&dir, &["--force", "-d", "@1000000000", name]);
+        let stamped = epoch(1_000_000_000, 0);
+        assert_eq!(times(&file), (stamped, stamped));
+        assert!(attribute.is_set(), "+{} was not restored", flag);
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn a_read_only_directory_is_named_when_a_file_cannot_be_created_in_it() {
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    if is_root() {
+        eprintln!("skipped: root may create files in a read-only directory");
+        return;
+    }
+    let dir = scratch_dir("permissions-directory");
+    fs::create_dir(dir.join("locked")).unwrap();
+    fs::set_permissions(dir.join("locked"), fs::Permissions::from_mode(0o555)).unwrap();
+
+    let output = common::run(&dir, &["locked/file"]);
+    fs::set_permissions(dir.join("locked"), fs::Permissions::from_mode(0o755)).unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "tick: cannot touch 'locked/file': Permission denied \
+         (cannot create files in directory 'locked')\n"
+    );
+}
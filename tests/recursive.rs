@@ -0,0 +1,75 @@
+mod common;
+
+use std::fs;
+
+use common::{epoch, run, scratch_dir, tick, times};
+
+#[cfg(unix)]
+#[test]
+fn a_directory_symlink_cycle_is_walked_once_with_a_warning() {
+    let dir = scratch_dir("recursive-cycle");
+    fs::create_dir_all(dir.join("tree/sub")).unwrap();
+    fs::write(dir.join("tree/sub/file"), "").unwrap();
+    std::os::unix::fs::symlink("..", dir.join("tree/sub/back")).unwrap();
+
+    let output = run(&dir, &["-R", "-d", "@1000000000", "tree"]);
+    assert!(output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("\"tree/sub/back\": directory already visited"),
+        "stderr: {}",
+        stderr
+    );
+    let stamped = epoch(1_000_000_000, 0);
+    for path in ["tree", "tree/sub", "tree/sub/file"] {
+        assert_eq!(times(&dir.join(path)), (stamped, stamped), "{}", path);
+    }
+}
+
+#[test]
+fn max_depth_stamps_the_last_level_without_descending_into_it() {
+    let dir = scratch_dir("recursive-max-depth");
+    fs::create_dir_all(dir.join("tree/sub/deep")).unwrap();
+    fs::write(dir.join("tree/file"), "").unwrap();
+    tick(&dir, &["-R", "-d", "@1000000000", "tree"]);
+
+    tick(
+        &dir,
+        &["-R", "--max-depth", "1", "-d", "@1500000000", "tree"],
+    );
+    let stamped = epoch(1_500_000_000, 0);
+    let left = epoch(1_000_000_000, 0);
+    for path in ["tree", "tree/file", "tree/sub"] {
+        assert_eq!(times(&dir.join(path)), (stamped, stamped), "{}", path);
+    }
+    assert_eq!(times(&dir.join("tree/sub/deep")), (left, left));
+}
+
+#[test]
+fn a_tree_is_walked_depth_first_in_name_order() {
+    let dir = scratch_dir("recursive-order");
+    for sub in ["tree/b/deep", "tree/a"] {
+        fs::create_dir_all(dir.join(sub)).unwrap();
+    }
+    for file in ["tree/c", "tree/b/f", "tree/b/deep/g"] {
+        fs::write(dir.join(file), "").unwrap();
+    }
+
+    let printed = tick(&dir, &["-R", "-v", "tree"]);
+    let expected: Vec<String> = [
+        "tree",
+        "tree/a",
+        "tree/b",
+        "tree/b/deep",
+        "tree/b/deep/g",
+        "tree/b/f",
+        "tree/c",
+    ]
+    .iter()
+    .map(|path| {
+        let path: std::path::PathBuf = path.split('/').collect();
+        format!("updated '{}'", path.display())
+    })
+    .collect();
+    assert_eq!(printed.lines().collect::<Vec<_>>(), expected);
+}
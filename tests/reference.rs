@@ -0,0 +1,102 @@
+mod common;
+
+use std::time::SystemTime;
+
+use common::{epoch, scratch_dir, tick, times};
+
+fn format_utc(time: SystemTime) -> String {
+    chrono::DateTime::<chrono::Utc>::from(time)
+        .format("%Y-%m-%dT%H:%M:%S%.9f%:z")
+        .to_string()
+}
+
+#[test]
+fn reference_times_are_copied_to_the_nanosecond() {
+    let dir = scratch_dir("reference-round-trip");
+    tick(&dir, &["-a", "-d", "@1000000000.123456789", "first"]);
+    tick(&dir, &["-m", "-d", "@1500000000.987654321", "first"]);
+    tick(&dir, &["second"]);
+
+    tick(&dir, &["-r", "first", "second"]);
+    let printed = tick(&dir, &["--print", "--utc", "--time=none", "second"]);
+
+    let (atime, mtime) = times(&dir.join("first"));
+    assert_ne!(atime, mtime);
+    assert_eq!(times(&dir.join("second")), (atime, mtime));
+    assert_eq!(
+        printed,
+        format!("{} {} second\n", format_utc(atime), format_utc(mtime))
+    );
+}
+
+#[test]
+fn a_reference_lends_only_the_times_selected() {
+    let dir = scratch_dir("reference-selected");
+    tick(&dir, &["-d", "@1000000000.25", "ref"]);
+    tick(&dir, &["-d", "@1500000000.75", "atime", "mtime"]);
+
+    tick(&dir, &["-a", "-r", "ref", "atime"]);
+    tick(&dir, &["-m", "-r", "ref", "mtime"]);
+    let referenced = epoch(1_000_000_000, 250_000_000);
+    let kept = epoch(1_500_000_000, 750_000_000);
+    assert_eq!(times(&dir.join("atime")), (referenced, kept));
+    assert_eq!(times(&dir.join("mtime")), (kept, referenced));
+}
+
+#[test]
+fn a_directory_lends_its_times_to_a_file_and_to_a_tree() {
+    let dir = scratch_dir("reference-directory");
+    std::fs::create_dir_all(dir.join("ref")).unwrap();
+    std::fs::create_dir_all(dir.join("tree/sub")).unwrap();
+    tick(&dir, &["-m", "-d", "@1500000000.5", "ref"]);
+    tick(&dir, &["-a", "-d", "@1000000000.5", "ref"]);
+    let lent = times(&dir.join("ref"));
+
+    tick(&dir, &["-r", "ref", "file"]);
+    tick(&dir, &["-R", "-r", "ref", "tree"]);
+    for path in ["file", "tree", "tree/sub"] {
+        assert_eq!(times(&dir.join(path)), lent, "{}", path);
+    }
+    assert_eq!(
+        lent,
+        (
+            epoch(1_000_000_000, 500_000_000),
+            epoch(1_500_000_000, 500_000_000)
+        )
+    );
+}
+
+#[test]
+fn reading_a_reference_leaves_its_access_time_alone() {
+    let dir = scratch_dir("reference-atime");
+    std::fs::write(dir.join("ref"), "contents").unwrap();
+    // An atime this old is one relatime would still move on a read.
+    tick(&dir, &["-d", "@1000000000", "ref"]);
+    let before = times(&dir.join("ref"));
+
+    tick(&dir, &["-r", "ref", "file"]);
+    tick(&dir, &["--print", "-r", "ref", "file"]);
+    assert_eq!(times(&dir.join("ref")), before);
+}
+
+// APFS keeps nanoseconds, and macOS's utimensat takes them.
+#[cfg(target_os = "macos")]
+#[test]
+fn reference_nanoseconds_survive_on_macos() {
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = scratch_dir("reference-macos-nanos");
+    tick(&dir, &["-a", "-d", "@1000000000.123456789", "ref"]);
+    tick(&dir, &["-m", "-d", "@1500000000.987654321", "ref"]);
+    tick(&dir, &["-r", "ref", "file"]);
+
+    let meta = std::fs::metadata(dir.join("file")).unwrap();
+    assert_eq!(
+        (meta.atime(), meta.atime_nsec()),
+        (1_000_000_000, 123_456_789)
+    );
+    assert_eq!(
+        (meta.mtime(), meta.mtime_nsec()),
+        (1_500_000_000, 987_654_321)
+    );
+}
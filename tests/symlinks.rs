@@ -0,0 +1,70 @@
+// Symlinks need privileges to make on Windows.
+#![cfg(unix)]
+
+mod common;
+
+use std::{fs, os::unix::fs::symlink, path::Path, time::SystemTime};
+
+use common::{epoch, scratch_dir, tick, times};
+
+/// The times a symlink has itself, not those of what it names.
+fn link_times(path: &Path) -> (SystemTime, SystemTime) {
+    let meta = fs::symlink_metadata(path).unwrap();
+    (meta.accessed().unwrap(), meta.modified().unwrap())
+}
+
+#[test]
+fn the_last_of_n_and_l_decides_whether_the_link_is_followed() {
+    let dir = scratch_dir("symlinks-n-l");
+    tick(&dir, &["-d", "@1000000000", "target"]);
+    symlink("target", dir.join("link")).unwrap();
+
+    tick(&dir, &["-n", "-L", "-d", "@1100000000", "link"]);
+    let followed = epoch(1_100_000_000, 0);
+    assert_eq!(times(&dir.join("target")), (followed, followed));
+    assert_ne!(link_times(&dir.join("link")).1, followed);
+
+    tick(&dir, &["-L", "-n", "-d", "@1200000000", "link"]);
+    let own = epoch(1_200_000_000, 0);
+    assert_eq!(link_times(&dir.join("link")), (own, own));
+    assert_eq!(times(&dir.join("target")), (followed, followed));
+}
+
+#[test]
+fn a_symlinked_reference_lends_its_own_times_under_n() {
+    let dir = scratch_dir("symlinks-reference");
+    tick(&dir, &["-d", "@1000000000", "target"]);
+    symlink("target", dir.join("ref")).unwrap();
+    tick(&dir, &["-n", "-d", "@1500000000", "ref"]);
+
+    // Following a link reads it, which can move its own atime: read it as it
+    // stands first.
+    tick(&dir, &["-n", "-r", "ref", "own"]);
+    tick(&dir, &["-r", "ref", "followed"]);
+    let target = epoch(1_000_000_000, 0);
+    let own = epoch(1_500_000_000, 0);
+    assert_eq!(times(&dir.join("followed")), (target, target));
+    assert_eq!(times(&dir.join("own")), (own, own));
+}
+
+#[test]
+fn relative_links_resolve_against_their_own_directory_not_the_cwd() {
+    let dir = scratch_dir("symlinks-relative");
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    fs::create_dir_all(dir.join("links")).unwrap();
+    tick(&dir, &["-d", "@1000000000", "sub/target"]);
+    symlink("target", dir.join("sub/link")).unwrap();
+    symlink("../sub/link", dir.join("links/chain")).unwrap();
+
+    tick(&dir, &["-d", "@1500000000", "links/chain"]);
+    let stamped = epoch(1_500_000_000, 0);
+    assert_eq!(times(&dir.join("sub/target")), (stamped, stamped));
+    assert!(!dir.join("target").exists());
+    assert!(!dir.join("links/target").exists());
+
+    // Through a link to a file that does not exist yet, the file is created
+    // where the link points.
+    symlink("../sub/new", dir.join("links/dangling")).unwrap();
+    tick(&dir, &["links/dangling"]);
+    assert!(dir.join("sub/new").is_file());
+}
@@ -0,0 +1,127 @@
+mod common;
+
+use std::time::{Duration, SystemTime};
+
+use common::{epoch, scratch_dir, tick, times};
+
+#[test]
+fn setting_only_the_atime_leaves_the_mtime_to_the_nanosecond() {
+    let dir = scratch_dir("times-omit-mtime");
+    tick(&dir, &["-d", "@1500000000.987654321", "file"]);
+
+    tick(&dir, &["-a", "-d", "@1000000000", "file"]);
+    assert_eq!(
+        times(&dir.join("file")),
+        (epoch(1_000_000_000, 0), epoch(1_500_000_000, 987_654_321))
+    );
+}
+
+#[test]
+fn a_negative_epoch_stamps_a_time_before_1970() {
+    let dir = scratch_dir("times-negative-epoch");
+    tick(&dir, &["-d", "@-86400.25", "file"]);
+    let before = SystemTime::UNIX_EPOCH - Duration::from_millis(86_400_250);
+    assert_eq!(times(&dir.join("file")), (before, before));
+}
+
+#[cfg(windows)]
+#[test]
+fn a_short_8_3_name_stamps_the_long_named_file() {
+    use std::os::windows::process::CommandExt;
+
+    let dir = scratch_dir("times-short-name");
+    let long = dir.join("a long file name.txt");
+    std::fs::write(&long, "").unwrap();
+    // cmd's %~sI gives the short form of each component that has one.
+    let output = std::process::Command::new("cmd")
+        .raw_arg(format!(
+            "/C for %I in (\"{}\") do @echo %~sI",
+            long.display()
+        ))
+        .output()
+        .unwrap();
+    let short = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let short = std::path::Path::new(&short);
+    if !output.status.success() || short.file_name() == long.file_name() {
+        eprintln!("skipped: this volume does not make 8.3 names");
+        return;
+    }
+
+    tick(&dir, &["-d", "@1000000000", short.to_str().unwrap()]);
+    assert_eq!(
+        times(&long),
+        (epoch(1_000_000_000, 0), epoch(1_000_000_000, 0))
+    );
+}
+
+#[test]
+fn auto_epoch_reads_a_bare_number_as_seconds_or_milliseconds() {
+    let dir = scratch_dir("times-auto-epoch");
+    tick(&dir, &["--auto-epoch", "-d", "1700000000", "seconds"]);
+    tick(&dir, &["--auto-epoch", "-d", "1700000000123", "millis"]);
+    let seconds = epoch(1_700_000_000, 0);
+    let millis = epoch(1_700_000_000, 123_000_000);
+    assert_eq!(times(&dir.join("seconds")), (seconds, seconds));
+    assert_eq!(times(&dir.join("millis")), (millis, millis));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn touch_ctime_moves_only_the_change_time() {
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = scratch_dir("times-ctime");
+    tick(&dir, &["-d", "@1000000000.5", "file"]);
+    let file = dir.join("file");
+    let ctime = |path: &std::path::Path| {
+        let meta = std::fs::metadata(path).unwrap();
+        (meta.ctime(), meta.ctime_nsec())
+    };
+    let before = ctime(&file);
+    // The kernel's clock for ctime is coarse: give it time to tick over.
+    std::thread::sleep(Duration::from_millis(50));
+
+    tick(&dir, &["--touch-ctime", "file"]);
+    assert!(ctime(&file) > before);
+    let stamped = epoch(1_000_000_000, 500_000_000);
+    assert_eq!(times(&file), (stamped, stamped));
+}
+
+#[test]
+fn time_none_creates_missing_files_but_sets_no_times() {
+    let dir = scratch_dir("times-none");
+    tick(&dir, &["-d", "@1500000000", "existing"]);
+    let started = SystemTime::now() - Duration::from_secs(1);
+
+    tick(
+        &dir,
+        &["--time=none", "-d", "@1000000000", "existing", "new"],
+    );
+    let kept = epoch(1_500_000_000, 0);
+    assert_eq!(times(&dir.join("existing")), (kept, kept));
+    let (atime, mtime) = times(&dir.join("new"));
+    assert!(atime >= started && mtime >= started);
+}
+
+#[test]
+fn date_file_takes_its_first_non_empty_line() {
+    let dir = scratch_dir("times-date-file");
+    std::fs::write(dir.join("when"), "\n  @1000000000.5  \n@1500000000\n").unwrap();
+    tick(&dir, &["--date-file", "when", "file"]);
+    let stamped = epoch(1_000_000_000, 500_000_000);
+    assert_eq!(times(&dir.join("file")), (stamped, stamped));
+}
+
+#[test]
+fn time_all_and_both_set_both_times_and_unknown_words_are_refused() {
+    let dir = scratch_dir("times-words");
+    for word in ["all", "both", "BOTH"] {
+        tick(&dir, &["-d", "@1000000000", word]);
+        tick(&dir, &["--time", word, "-d", "@1500000000", word]);
+        let stamped = epoch(1_500_000_000, 0);
+        assert_eq!(times(&dir.join(word)), (stamped, stamped), "{}", word);
+    }
+    let output = common::run(&dir, &["--time=every", "file"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(!dir.join("file").exists());
+}